@@ -0,0 +1,252 @@
+use std::cmp::Reverse;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::git::read_file_at;
+use crate::version::Version;
+
+/// Changelog file names, looked up in the addon folder first, then the repository root
+const CHANGELOG_FILES: &[&str] = &["CHANGELOG.md", "Changelog.md", "changelog.md", "CHANGES.md"];
+
+/// A version section of a changelog
+#[derive(Debug)]
+pub struct Section {
+    pub title: String,
+    pub version: Version,
+    pub lines: Vec<String>,
+}
+
+/// Read the changelog of an addon at a given revision
+pub fn load_at(repo_dir: &Path, rev: &str, addon_path: &str) -> Option<String> {
+    [addon_path, ""].iter().find_map(|dir| {
+        CHANGELOG_FILES.iter().find_map(|file| {
+            let path = if dir.is_empty() {
+                file.to_string()
+            } else {
+                format!("{}/{}", dir, file)
+            };
+            read_file_at(repo_dir, rev, &path)
+        })
+    })
+}
+
+/// Split a markdown changelog into version sections.
+///
+/// A section starts at any heading containing a version, such as
+/// `## [1.2.0] - 2024-01-01` or `# v1.2.0`. Headings without a version
+/// (e.g. `## Unreleased` or `## 2024-01-01`) end the previous section.
+pub fn parse(content: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut section_level = None;
+    let mut current: Option<Section> = None;
+
+    for line in content.lines() {
+        let level = line.chars().take_while(|c| *c == '#').count();
+        let is_heading = level > 0 && line[level..].starts_with(' ');
+
+        if is_heading && section_level.is_none_or(|l| level <= l) {
+            let title = line[level..].trim();
+            let version = title
+                .split(|c: char| c.is_whitespace() || c == '[' || c == ']' || c == '(')
+                .find_map(|word| {
+                    (word.starts_with(|c: char| c.is_ascii_digit() || c == 'v' || c == 'V')
+                        && !is_date(word))
+                    .then(|| Version::parse(word))
+                    .flatten()
+                });
+
+            if let Some(section) = current.take() {
+                sections.push(section);
+            }
+            if let Some(version) = version {
+                section_level = Some(level);
+                current = Some(Section {
+                    title: title.to_string(),
+                    version,
+                    lines: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        if let Some(section) = current.as_mut() {
+            section.lines.push(line.to_string());
+        }
+    }
+
+    if let Some(section) = current {
+        sections.push(section);
+    }
+
+    for section in &mut sections {
+        while section.lines.last().is_some_and(|l| l.trim().is_empty()) {
+            section.lines.pop();
+        }
+        while section.lines.first().is_some_and(|l| l.trim().is_empty()) {
+            section.lines.remove(0);
+        }
+    }
+
+    sections
+}
+
+/// Check if a word is a year or a date like `2024`, `2024-01-01` or `2024/01/01`
+fn is_date(word: &str) -> bool {
+    let year_len = word.chars().take_while(|c| c.is_ascii_digit()).count();
+    year_len == 4
+        && word[4..]
+            .chars()
+            .next()
+            .is_none_or(|c| c == '-' || c == '/')
+}
+
+/// Get the sections newer than `from` and up to `to`, newest first
+pub fn between(sections: Vec<Section>, from: Option<Version>, to: Option<Version>) -> Vec<Section> {
+    let mut sections: Vec<Section> = sections
+        .into_iter()
+        .filter(|s| from.is_none_or(|from| s.version > from))
+        .filter(|s| to.is_none_or(|to| s.version <= to))
+        .collect();
+    sections.sort_by_key(|s| Reverse(s.version));
+    sections
+}
+
+/// Check if a changelog line announces a breaking change
+fn is_breaking(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    lower.contains("breaking") || lower.contains("!:")
+}
+
+/// Print changelog sections, highlighting breaking changes
+pub fn print_sections(sections: &[Section], indent: &str) {
+    for section in sections {
+        status!("{}{}", indent, section.title.bold());
+
+        // Everything under a "Breaking" sub-heading is breaking
+        let mut in_breaking_block = false;
+        for line in &section.lines {
+            if line.starts_with('#') {
+                in_breaking_block = is_breaking(line);
+            }
+            if in_breaking_block || is_breaking(line) {
+                status!("{}{}", indent, line.red().bold());
+            } else {
+                status!("{}{}", indent, line);
+            }
+        }
+        status!();
+    }
+}
+
+/// Check if any of the sections contain breaking changes
+pub fn has_breaking_changes(sections: &[Section]) -> bool {
+    sections
+        .iter()
+        .any(|s| s.lines.iter().any(|l| is_breaking(l)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog
+
+## Unreleased
+- Not released yet
+
+## [1.2.0] - 2024-03-01
+### Breaking
+- Renamed signals
+
+## v1.1.0 (2024/01/15)
+
+- Fixed a crash
+
+## 1.0.0
+- First release
+";
+
+    fn versions(sections: &[Section]) -> Vec<String> {
+        sections.iter().map(|s| s.version.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_sections() {
+        let sections = parse(CHANGELOG);
+        assert_eq!(versions(&sections), ["1.2.0", "1.1.0", "1.0.0"]);
+        assert_eq!(sections[0].title, "[1.2.0] - 2024-03-01");
+        assert_eq!(sections[0].lines, ["### Breaking", "- Renamed signals"]);
+        // Blank lines around the content are trimmed
+        assert_eq!(sections[1].lines, ["- Fixed a crash"]);
+    }
+
+    #[test]
+    fn parse_skips_date_headings() {
+        let sections =
+            parse("## 2024-05-01\n- Dated entry\n\n## 2024\n- Year\n\n## v2.0.0\n- Major\n");
+        assert_eq!(versions(&sections), ["2.0.0"]);
+        assert_eq!(sections[0].lines, ["- Major"]);
+
+        // The version after a date is still found
+        let sections = parse("## 2024-05-01 - 3.1.0\n- Entry\n");
+        assert_eq!(versions(&sections), ["3.1.0"]);
+    }
+
+    #[test]
+    fn parse_keeps_sub_headings_in_sections() {
+        let sections = parse("# v1.0.0\n## Added 2.0 support\n- x\n# v0.9.0\n- y\n");
+        assert_eq!(versions(&sections), ["1.0.0", "0.9.0"]);
+        assert_eq!(sections[0].lines, ["## Added 2.0 support", "- x"]);
+    }
+
+    #[test]
+    fn parse_without_versions() {
+        assert!(parse("# Changelog\nNothing here\n").is_empty());
+        assert!(parse("").is_empty());
+    }
+
+    #[test]
+    fn is_date_words() {
+        assert!(is_date("2024"));
+        assert!(is_date("2024-01-01"));
+        assert!(is_date("2024/01/01"));
+        assert!(!is_date("1.2.0"));
+        assert!(!is_date("20240"));
+        assert!(!is_date("2024.1"));
+        assert!(!is_date("v2024"));
+    }
+
+    #[test]
+    fn between_versions() {
+        let range = |from: Option<&str>, to: Option<&str>| {
+            versions(&between(
+                parse(CHANGELOG),
+                from.and_then(Version::parse),
+                to.and_then(Version::parse),
+            ))
+        };
+        assert_eq!(range(Some("1.0.0"), Some("1.2.0")), ["1.2.0", "1.1.0"]);
+        assert_eq!(range(Some("1.0.0"), Some("1.1.5")), ["1.1.0"]);
+        assert_eq!(range(None, Some("1.1.0")), ["1.1.0", "1.0.0"]);
+        assert_eq!(range(Some("1.2.0"), None), Vec::<String>::new());
+        assert_eq!(range(None, None), ["1.2.0", "1.1.0", "1.0.0"]);
+    }
+
+    #[test]
+    fn between_sorts_newest_first() {
+        let sections = parse("## 1.0.0\n## 1.2.0\n## 1.1.0\n");
+        assert_eq!(
+            versions(&between(sections, None, None)),
+            ["1.2.0", "1.1.0", "1.0.0"]
+        );
+    }
+
+    #[test]
+    fn breaking_changes() {
+        let sections = parse(CHANGELOG);
+        assert!(has_breaking_changes(&sections[..1]));
+        assert!(!has_breaking_changes(&sections[1..]));
+        assert!(is_breaking("- feat!: new API"));
+    }
+}
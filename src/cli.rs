@@ -1,4 +1,9 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::config::UpgradePolicy;
+use crate::output::Format;
 
 #[derive(Parser)]
 #[command(name = "gdam")]
@@ -8,6 +13,18 @@ use clap::{Parser, Subcommand};
     about = "Godot Addon Manager - A package manager for Godot addons"
 )]
 pub struct Cli {
+    /// Run in this project directory instead of the one found from the current directory
+    #[arg(short = 'C', long = "project", value_name = "DIR", global = true)]
+    pub project: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, global = true, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Print results as JSON, same as --format json
+    #[arg(long, global = true)]
+    pub json: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -17,28 +34,131 @@ pub enum Commands {
     /// Show version information
     Version,
 
-    /// Initialize a new gdam project in current directory
-    Init,
+    /// Initialize a new gdam project
+    Init {
+        /// Adopt the proposed source of every existing addon without asking
+        #[arg(long, short)]
+        yes: bool,
+
+        /// Commit addons to the repository instead of ignoring them in .gitignore
+        #[arg(long)]
+        vendor: bool,
+
+        /// Registry index file or git URL, saved to gdam.yaml and used to identify existing addons
+        #[arg(long = "registry", value_name = "LOCATION")]
+        registries: Vec<String>,
+    },
 
     /// Install addons
     Install {
-        /// Git repository URL (optional, installs all dependencies if not provided)
+        /// Git repository URL or registry name (optional, installs all dependencies if not provided)
         git_url: Option<String>,
+
+        /// Branch, tag or commit to install (defaults to the line matching the project's Godot version)
+        #[arg(long = "ref", requires = "git_url")]
+        git_ref: Option<String>,
+
+        /// Run post-install hooks declared by the installed packages
+        #[arg(long)]
+        run_hooks: bool,
+
+        /// Replace addons of the same name installed from another source
+        #[arg(long, requires = "git_url")]
+        force: bool,
+
+        /// Install the addon under another folder name
+        #[arg(long = "as", value_name = "NAME", requires = "git_url")]
+        alias: Option<String>,
+
+        /// Upgrade policy of the installed addon(s)
+        #[arg(long, value_enum, requires = "git_url")]
+        policy: Option<UpgradePolicy>,
+    },
+
+    /// Start managing an addon already in addons/, locked to the commit its files came from
+    Adopt {
+        /// Addon folder name in addons/
+        addon_name: String,
+
+        /// Git repository URL the addon came from
+        git_url: String,
+    },
+
+    /// Add the addons managed by git submodules or another addon manager to gdam.yaml
+    Import {
+        /// Where the addons are currently declared
+        #[arg(long, value_enum)]
+        from: ImportSource,
+
+        /// Remove the old mechanism afterwards and install the addons with gdam
+        #[arg(long)]
+        remove_old: bool,
     },
 
     /// List installed addons
     List,
 
+    /// Search the configured registries for addons
+    Search {
+        /// Text to look for in addon names, descriptions and tags
+        term: String,
+    },
+
     /// Uninstall an addon
     Uninstall {
         /// Addon name or source URL to uninstall
         name: String,
     },
 
+    /// Show addons with updates available, without modifying anything
+    Outdated,
+
     /// Upgrade installed addons to latest version
     Upgrade {
         /// Addon name to upgrade (optional, upgrades all if not provided)
         addon_name: Option<String>,
+
+        /// Show commits and file changes of each update without applying them
+        #[arg(long, conflicts_with = "confirm")]
+        dry_run: bool,
+
+        /// Show the changes of each update and ask before applying it
+        #[arg(long, short)]
+        confirm: bool,
+
+        /// Move the addon to this branch, tag or commit instead, even an older one
+        #[arg(long, value_name = "REF", requires = "addon_name")]
+        to: Option<String>,
+    },
+
+    /// Check that the files in addons/ are identical to the commits locked in gdam.yaml
+    Verify,
+
+    /// Rewrite the gdam block of .gitignore from gdam.yaml
+    Gitignore {
+        /// Only check that .gitignore matches gdam.yaml, failing if it doesn't
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Restore the addons changed by the last install or upgrade to their previous commit
+    Rollback {
+        /// Only roll back the last change of this addon (and addons from the same source)
+        addon_name: Option<String>,
+    },
+
+    /// Show the changelog of an addon between two versions
+    Changelog {
+        /// Addon name
+        addon_name: String,
+
+        /// Show changes after this version (defaults to the installed version)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Show changes up to this version (defaults to the latest version)
+        #[arg(long)]
+        to: Option<String>,
     },
 
     /// Manage cache
@@ -46,6 +166,29 @@ pub enum Commands {
         #[command(subcommand)]
         action: CacheAction,
     },
+
+    /// Show and edit user settings (~/.config/gdam/config.yaml)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Tools for addon authors
+    Package {
+        #[command(subcommand)]
+        action: PackageAction,
+    },
+}
+
+/// Mechanisms `gdam import` reads addons from
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportSource {
+    /// Git submodules declared in .gitmodules
+    Submodules,
+    /// plug() calls in gd-plug's plug.gd
+    GdPlug,
+    /// Dependencies in the package.json of godot-package-manager
+    Gpm,
 }
 
 #[derive(Subcommand)]
@@ -56,3 +199,33 @@ pub enum CacheAction {
     /// Clear cache directory
     Clear,
 }
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective value of a setting
+    Get {
+        key: String,
+
+        /// Also print where the value comes from
+        #[arg(long)]
+        show_origin: bool,
+    },
+
+    /// Set a setting in the user configuration file
+    Set { key: String, value: String },
+
+    /// Remove a setting from the user configuration file
+    Unset { key: String },
+
+    /// List every setting with its effective value and where it comes from
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum PackageAction {
+    /// Validate the gdam-package.yaml of an addon repository
+    Validate {
+        /// Repository directory (defaults to the current directory or --project)
+        path: Option<PathBuf>,
+    },
+}
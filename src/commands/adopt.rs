@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::config::{ADDONS_DIR, Addon, Config};
+use crate::error::{Error, Result};
+use crate::git::{
+    commits_touching, ensure_repo, exact_tag, expand_source, latest_rev, list_files_at,
+    repo_cache_dir, short_hash,
+};
+use crate::gitignore;
+use crate::package::{
+    addon_version_at, compare_addon_files, hash_addon_files, manifest_at, provided_addon_names_at,
+    provided_addon_path_at,
+};
+use crate::plugin::parse_plugin_cfg;
+use crate::project::Project;
+use crate::utils::{confirm, get_cache_dir};
+
+/// An addon on disk matched to a commit of its source repository
+pub struct Adoption {
+    /// Entry to add to gdam.yaml
+    pub addon: Addon,
+    /// Files differing from the matched commit, empty for an exact match
+    pub diff: Vec<(char, String)>,
+    /// Tag or version of the matched commit
+    pub description: String,
+}
+
+/// Start managing an addon already in `addons/`, locked to the commit its files came from
+pub fn execute(project: &Project, addon_name: String, source: String) -> Result<()> {
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let mut config = Config::load(project)?;
+
+    if config.addons.iter().any(|a| a.name == addon_name) {
+        return Err(Error::Other(format!(
+            "Addon '{}' is already managed by gdam",
+            addon_name
+        )));
+    }
+
+    let source = expand_source(&source, &config.source_schemes).ok_or(Error::InvalidUrl(source))?;
+    let adoption = match_addon(project, &addon_name, &source, &config)?;
+    print_adoption(&adoption, "");
+    if !adoption.diff.is_empty()
+        && !confirm(&format!(
+            "Adopt {} at commit {}?",
+            addon_name,
+            short_hash(&adoption.addon.checksum)
+        ))?
+    {
+        status!("Nothing was changed.");
+        return Ok(());
+    }
+
+    config.addons.push(adoption.addon);
+    config.save(project)?;
+    gitignore::sync(project, &config)?;
+
+    status!(
+        "{}",
+        format!("Adopted '{}' successfully!", addon_name).green()
+    );
+    Ok(())
+}
+
+/// Find the commit of a source repository whose addon files match `addons/<addon_name>`
+/// exactly, or the closest one
+pub fn match_addon(
+    project: &Project,
+    addon_name: &str,
+    source: &str,
+    config: &Config,
+) -> Result<Adoption> {
+    let addon_dir = project.addons_dir().join(addon_name);
+    if !addon_dir.is_dir() {
+        return Err(Error::Other(format!(
+            "{}/{} does not exist",
+            ADDONS_DIR, addon_name
+        )));
+    }
+
+    let clone_dir = repo_cache_dir(&get_cache_dir()?, source)?;
+
+    status!("Fetching {}...", source);
+    ensure_repo(project, source, &clone_dir)?;
+
+    let latest = latest_rev(&clone_dir, None)?;
+    let upstream_name = find_upstream_name(&clone_dir, &latest, addon_name, source)?;
+
+    let local = hash_addon_files(&addon_dir)?;
+
+    // Search the history of the addon for the closest tree
+    let mut addon_paths = vec![provided_addon_path_at(&clone_dir, &latest, &upstream_name)];
+    let default_path = format!("{}/{}", ADDONS_DIR, upstream_name);
+    if !addon_paths.contains(&default_path) {
+        addon_paths.push(default_path);
+    }
+    let addon_paths: Vec<&str> = addon_paths.iter().map(String::as_str).collect();
+    let commits = commits_touching(&clone_dir, &addon_paths)?;
+
+    let exclude = manifest_at(&clone_dir, &latest)
+        .map(|m| m.exclude)
+        .unwrap_or_default();
+    let mut best: Option<(&String, Vec<(char, String)>)> = None;
+    for commit in &commits {
+        let path = provided_addon_path_at(&clone_dir, commit, &upstream_name);
+        let upstream = list_files_at(&clone_dir, commit, &path)?;
+        let diff = compare_addon_files(&local, &upstream, &exclude);
+        let exact = diff.is_empty();
+        if best.as_ref().is_none_or(|(_, d)| diff.len() < d.len()) {
+            best = Some((commit, diff));
+        }
+        if exact {
+            break;
+        }
+    }
+    let Some((commit, diff)) = best else {
+        return Err(Error::AddonMissingInRepo(format!(
+            "'{}' not found in the history of {}",
+            upstream_name, source
+        )));
+    };
+
+    let description = exact_tag(&clone_dir, commit)
+        .or_else(|| addon_version_at(&clone_dir, commit, &upstream_name))
+        .unwrap_or_default();
+
+    let manifest = manifest_at(&clone_dir, commit);
+    let version = parse_plugin_cfg(&addon_dir.join("plugin.cfg"))
+        .version
+        .or_else(|| manifest.as_ref().and_then(|m| m.version.clone()))
+        .unwrap_or_default();
+    let dependencies = manifest
+        .map(|m| m.dependencies)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|d| expand_source(&d.source, &config.source_schemes))
+        .collect();
+
+    Ok(Adoption {
+        addon: Addon {
+            name: addon_name.to_string(),
+            version,
+            source: source.to_string(),
+            checksum: commit.clone(),
+            track: None,
+            transitive: false,
+            dependencies,
+            upstream_name: (upstream_name != addon_name).then_some(upstream_name),
+            policy: None,
+        },
+        diff,
+        description,
+    })
+}
+
+/// Print the matched commit, and the differing files if it is not an exact match
+pub fn print_adoption(adoption: &Adoption, indent: &str) {
+    let addon = &adoption.addon;
+    let description = if adoption.description.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", adoption.description)
+    };
+
+    if adoption.diff.is_empty() {
+        status!(
+            "{}{}",
+            indent,
+            format!(
+                "addons/{} matches commit {}{}",
+                addon.name,
+                short_hash(&addon.checksum),
+                description
+            )
+            .green()
+        );
+        return;
+    }
+
+    status!(
+        "{}{}",
+        indent,
+        format!(
+            "No commit matches addons/{} exactly. Closest is {}{}, {} file(s) differ:",
+            addon.name,
+            short_hash(&addon.checksum),
+            description,
+            adoption.diff.len()
+        )
+        .yellow()
+    );
+    for (status, file) in &adoption.diff {
+        let line = match status {
+            'A' => format!("  A {} (only on disk)", file).green(),
+            'D' => format!("  D {} (missing on disk)", file).red(),
+            _ => format!("  M {}", file).yellow(),
+        };
+        status!("{}{}", indent, line);
+    }
+}
+
+/// Find the addon folder in the repository matching a local addon: the same
+/// name, or the only addon the repository provides
+fn find_upstream_name(repo_dir: &Path, rev: &str, name: &str, source: &str) -> Result<String> {
+    let names = provided_addon_names_at(repo_dir, rev)?;
+    if names.iter().any(|n| n == name) {
+        return Ok(name.to_string());
+    }
+    match names.as_slice() {
+        [only] => {
+            status!("Using addons/{} of {}", only, source);
+            Ok(only.clone())
+        }
+        _ => Err(Error::AddonMissingInRepo(format!(
+            "{} does not provide '{}' (provides: {})",
+            source,
+            name,
+            names.join(", ")
+        ))),
+    }
+}
@@ -1,9 +1,11 @@
 use std::fs;
 
 use colored::Colorize;
+use serde::Serialize;
 
 use crate::cli::CacheAction;
 use crate::error::Result;
+use crate::output;
 use crate::utils::get_cache_dir;
 
 pub fn execute(action: CacheAction) -> Result<()> {
@@ -13,9 +15,23 @@ pub fn execute(action: CacheAction) -> Result<()> {
     }
 }
 
+#[derive(Serialize)]
+struct CachePath {
+    path: std::path::PathBuf,
+}
+
+#[derive(Serialize)]
+struct CacheCleared {
+    /// Bytes removed from the cache
+    freed: u64,
+}
+
 fn print_path() -> Result<()> {
     let cache_dir = get_cache_dir()?;
-    println!("{}", cache_dir.display());
+    if output::is_json() {
+        return output::print_json(&CachePath { path: cache_dir });
+    }
+    status!("{}", cache_dir.display());
     Ok(())
 }
 
@@ -23,7 +39,10 @@ fn clear_cache() -> Result<()> {
     let cache_dir = get_cache_dir()?;
 
     if !cache_dir.exists() {
-        println!("Cache directory does not exist.");
+        if output::is_json() {
+            return output::print_json(&CacheCleared { freed: 0 });
+        }
+        status!("Cache directory does not exist.");
         return Ok(());
     }
 
@@ -33,7 +52,11 @@ fn clear_cache() -> Result<()> {
     fs::remove_dir_all(&cache_dir)?;
     fs::create_dir_all(&cache_dir)?;
 
-    println!(
+    if output::is_json() {
+        return output::print_json(&CacheCleared { freed: size });
+    }
+
+    status!(
         "{}",
         format!("Cache cleared! Freed {}", format_size(size)).green()
     );
@@ -0,0 +1,134 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::changelog;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::git::{Track, ensure_repo, latest_rev, repo_cache_dir, rev_parse};
+use crate::output;
+use crate::package::{addon_version_at, provided_addon_path_at};
+use crate::project::Project;
+use crate::utils::get_cache_dir;
+use crate::version::Version;
+
+#[derive(Serialize)]
+struct ChangelogOutput {
+    addon: String,
+    /// Versions after `from` and up to `to` are included, unbounded if none
+    from: Option<String>,
+    to: Option<String>,
+    /// Only the latest version is included because the installed one is unknown
+    latest_only: bool,
+    breaking: bool,
+    sections: Vec<SectionOutput>,
+}
+
+#[derive(Serialize)]
+struct SectionOutput {
+    title: String,
+    version: String,
+    breaking: bool,
+    lines: Vec<String>,
+}
+
+pub fn execute(
+    project: &Project,
+    addon_name: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<()> {
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let config = Config::load(project)?;
+    let addon = config
+        .addons
+        .iter()
+        .find(|a| a.name == addon_name)
+        .ok_or_else(|| Error::AddonNotFound(format!("'{}' in gdam.yaml", addon_name)))?;
+
+    let clone_dir = repo_cache_dir(&get_cache_dir()?, &addon.source)?;
+    ensure_repo(project, &addon.source, &clone_dir)?;
+
+    // The latest changelog covers every version
+    let track = addon.track.as_deref().map(Track::parse);
+    let latest = rev_parse(&clone_dir, &latest_rev(&clone_dir, track.as_ref())?)?;
+    let addon_path = provided_addon_path_at(&clone_dir, &latest, addon.upstream_name());
+
+    let content = changelog::load_at(&clone_dir, &latest, &addon_path)
+        .ok_or_else(|| Error::Other(format!("No changelog found for addon '{}'", addon_name)))?;
+
+    // Default to the changes between the installed and the latest version,
+    // or only the latest version if the installed one is unknown
+    let latest_only = from.is_none() && Version::parse(&addon.version).is_none();
+    let from = from.unwrap_or_else(|| addon.version.clone());
+    let to = match to {
+        Some(to) => Some(to),
+        None => addon_version_at(&clone_dir, &latest, addon.upstream_name()),
+    };
+    let from_version = parse_bound(&from, "--from")?;
+    let to_version = match &to {
+        Some(to) => parse_bound(to, "--to")?,
+        None => None,
+    };
+
+    let mut sections = changelog::between(changelog::parse(&content), from_version, to_version);
+    if latest_only {
+        sections.truncate(1);
+    }
+
+    if output::is_json() {
+        return output::print_json(&ChangelogOutput {
+            addon: addon.name.clone(),
+            from: from_version.map(|v| v.to_string()),
+            to: to_version.map(|v| v.to_string()),
+            latest_only,
+            breaking: changelog::has_breaking_changes(&sections),
+            sections: sections
+                .into_iter()
+                .map(|section| SectionOutput {
+                    breaking: changelog::has_breaking_changes(std::slice::from_ref(&section)),
+                    title: section.title,
+                    version: section.version.to_string(),
+                    lines: section.lines,
+                })
+                .collect(),
+        });
+    }
+
+    match (&from_version, &to_version) {
+        _ if latest_only => status!(
+            "Changes in the latest version of {} (installed version unknown, use --from to see more):\n",
+            addon.name
+        ),
+        (Some(from), Some(to)) => status!("Changes in {} from {} to {}:\n", addon.name, from, to),
+        (Some(from), None) => status!("Changes in {} since {}:\n", addon.name, from),
+        _ => status!("Changes in {}:\n", addon.name),
+    }
+
+    if sections.is_empty() {
+        status!("{}", "No changelog entries in this range.".dimmed());
+        return Ok(());
+    }
+
+    if changelog::has_breaking_changes(&sections) {
+        status!(
+            "{}\n",
+            "These changes include breaking changes!".red().bold()
+        );
+    }
+    changelog::print_sections(&sections, "");
+
+    Ok(())
+}
+
+/// Parse a version bound, an empty bound means unbounded
+fn parse_bound(value: &str, option: &str) -> Result<Option<Version>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    Version::parse(value)
+        .map(Some)
+        .ok_or_else(|| Error::Other(format!("Invalid version for {}: '{}'", option, value)))
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::cli::ConfigAction;
+use crate::error::{Error, Result};
+use crate::git::redact;
+use crate::output;
+use crate::settings::{self, KEYS, find_key, read_file, settings_file, token_env_var, write_file};
+
+/// The effective value of a setting
+#[derive(Serialize)]
+struct SettingOutput {
+    key: &'static str,
+    value: Option<String>,
+    /// `default`, the settings file or the environment variable the value comes from
+    origin: Option<String>,
+    env: &'static str,
+}
+
+/// A change to the settings file
+#[derive(Serialize)]
+struct ChangeOutput {
+    key: &'static str,
+    /// The new value, none when the setting was removed
+    value: Option<String>,
+    file: PathBuf,
+    changed: bool,
+}
+
+#[derive(Serialize)]
+struct ListOutput<'a> {
+    settings: Vec<SettingOutput>,
+    rewrites: BTreeMap<&'a str, String>,
+    mirrors: BTreeMap<&'a str, Vec<String>>,
+    auth: Vec<AuthOutput<'a>>,
+}
+
+/// Where the credentials of a host come from, never the token itself
+#[derive(Serialize)]
+struct AuthOutput<'a> {
+    host: &'a str,
+    token_origin: Option<String>,
+    ssh_key: Option<PathBuf>,
+}
+
+pub fn execute(action: ConfigAction) -> Result<()> {
+    match action {
+        ConfigAction::Get { key, show_origin } => get(&key, show_origin),
+        ConfigAction::Set { key, value } => set(&key, &value),
+        ConfigAction::Unset { key } => unset(&key),
+        ConfigAction::List => list(),
+    }
+}
+
+fn get(name: &str, show_origin: bool) -> Result<()> {
+    let key = find_key(name)?;
+    let Some((value, origin)) = settings::current().effective(key.name) else {
+        return Err(Error::Other(format!("{} is not set", key.name)));
+    };
+    if output::is_json() {
+        return output::print_json(&SettingOutput {
+            key: key.name,
+            value: Some(value),
+            origin: Some(origin.to_string()),
+            env: key.env,
+        });
+    }
+    if show_origin {
+        status!("{}\t{}", origin, value);
+    } else {
+        status!("{}", value);
+    }
+    Ok(())
+}
+
+fn set(name: &str, value: &str) -> Result<()> {
+    let key = find_key(name)?;
+    let parsed = key.parse(value)?;
+
+    let mut values = read_file()?;
+    values.insert(key.name.to_string(), parsed);
+    write_file(&values)?;
+    if output::is_json() {
+        output::print_json(&ChangeOutput {
+            key: key.name,
+            value: Some(value.to_string()),
+            file: settings_file()?,
+            changed: true,
+        })?;
+    }
+    status!(
+        "Set {} = {} in {}",
+        key.name,
+        value,
+        settings_file()?.display()
+    );
+
+    warn_if_overridden(key.env);
+    Ok(())
+}
+
+fn unset(name: &str) -> Result<()> {
+    let key = find_key(name)?;
+
+    let mut values = read_file()?;
+    let changed = values.remove(key.name).is_some();
+    if changed {
+        write_file(&values)?;
+    }
+    if output::is_json() {
+        output::print_json(&ChangeOutput {
+            key: key.name,
+            value: None,
+            file: settings_file()?,
+            changed,
+        })?;
+    }
+    if !changed {
+        status!("{} is not set in {}", key.name, settings_file()?.display());
+        return Ok(());
+    }
+    status!("Removed {} from {}", key.name, settings_file()?.display());
+
+    warn_if_overridden(key.env);
+    Ok(())
+}
+
+fn list() -> Result<()> {
+    let settings = settings::current();
+    if output::is_json() {
+        return output::print_json(&ListOutput {
+            settings: KEYS
+                .iter()
+                .map(|key| {
+                    let effective = settings.effective(key.name);
+                    SettingOutput {
+                        key: key.name,
+                        origin: effective.as_ref().map(|(_, origin)| origin.to_string()),
+                        value: effective.map(|(value, _)| value),
+                        env: key.env,
+                    }
+                })
+                .collect(),
+            rewrites: settings
+                .rewrites()
+                .iter()
+                .map(|(prefix, replacement)| (prefix.as_str(), redact(replacement)))
+                .collect(),
+            mirrors: settings
+                .mirrors()
+                .iter()
+                .map(|(source, mirrors)| {
+                    (source.as_str(), mirrors.iter().map(|m| redact(m)).collect())
+                })
+                .collect(),
+            auth: settings
+                .auth_hosts()
+                .map(|host| AuthOutput {
+                    host,
+                    token_origin: settings.token(host).map(|t| t.origin.to_string()),
+                    ssh_key: settings.ssh_key(host),
+                })
+                .collect(),
+        });
+    }
+    for key in KEYS {
+        match settings.effective(key.name) {
+            Some((value, origin)) => status!(
+                "{} = {} {}",
+                key.name.bold(),
+                value,
+                format!("({})", origin).dimmed()
+            ),
+            None => status!("{} {}", key.name.bold(), "(not set)".dimmed()),
+        }
+        status!("    {} Override with {}.", key.description, key.env);
+    }
+
+    for (prefix, replacement) in settings.rewrites() {
+        status!("{} {} -> {}", "rewrite".bold(), prefix, redact(replacement));
+    }
+    for (source, mirrors) in settings.mirrors() {
+        let mirrors: Vec<String> = mirrors.iter().map(|m| redact(m)).collect();
+        status!("{} {} -> {}", "mirrors".bold(), source, mirrors.join(", "));
+    }
+    // Only say where tokens come from, never show them
+    for host in settings.auth_hosts() {
+        let mut parts = Vec::new();
+        let ssh_key = settings.ssh_key(host);
+        match settings.token(host) {
+            Some(token) => parts.push(format!("token from {}", token.origin)),
+            None if ssh_key.is_none() => {
+                parts.push(format!("no token, {} is not set", token_env_var(host)))
+            }
+            None => {}
+        }
+        if let Some(key) = ssh_key {
+            parts.push(format!("ssh key {}", key.display()));
+        }
+        status!("{} {}: {}", "auth".bold(), host, parts.join(", "));
+    }
+    Ok(())
+}
+
+/// Tell the user when an environment variable takes precedence over the file
+fn warn_if_overridden(env: &str) {
+    if std::env::var(env).is_ok_and(|v| !v.is_empty()) {
+        status!(
+            "{}",
+            format!("  Note: {} is set and takes precedence over the file", env).yellow()
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::gitignore::{self, GITIGNORE_FILE};
+use crate::output;
+use crate::project::Project;
+
+#[derive(Debug, Serialize)]
+struct GitignoreOutput {
+    /// Whether .gitignore already matched gdam.yaml
+    up_to_date: bool,
+    /// Whether .gitignore was rewritten, never with `--check`
+    updated: bool,
+    /// Lines missing from .gitignore
+    added: Vec<String>,
+    /// Lines .gitignore should no longer have
+    removed: Vec<String>,
+}
+
+/// Rewrite the gdam block of .gitignore from gdam.yaml, or only check it with `check`.
+/// Returns whether .gitignore matched gdam.yaml.
+pub fn execute(project: &Project, check: bool) -> Result<bool> {
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let config = Config::load(project)?;
+    let content = gitignore::read(project)?;
+    let rendered = gitignore::render(&content, &config);
+
+    let removed: Vec<String> = content
+        .lines()
+        .filter(|l| !rendered.lines().any(|r| r == *l))
+        .map(String::from)
+        .collect();
+    let added: Vec<String> = rendered
+        .lines()
+        .filter(|l| !content.lines().any(|c| c == *l))
+        .map(String::from)
+        .collect();
+    let up_to_date = rendered == content;
+    let updated = !up_to_date && !check && gitignore::sync(project, &config)?;
+
+    if output::is_json() {
+        output::print_json(&GitignoreOutput {
+            up_to_date,
+            updated,
+            added,
+            removed,
+        })?;
+        return Ok(up_to_date);
+    }
+
+    if up_to_date {
+        status!("{} is up to date.", GITIGNORE_FILE);
+        return Ok(true);
+    }
+
+    if check {
+        for line in &removed {
+            status!("{}", format!("- {}", line).red());
+        }
+        for line in &added {
+            status!("{}", format!("+ {}", line).green());
+        }
+        eprintln!(
+            "{}",
+            format!(
+                "{} does not match gdam.yaml. Run 'gdam gitignore' to update it",
+                GITIGNORE_FILE
+            )
+            .red()
+        );
+        return Ok(false);
+    }
+
+    status!("{}", format!("Updated {}", GITIGNORE_FILE).green());
+    Ok(false)
+}
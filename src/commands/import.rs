@@ -0,0 +1,659 @@
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::cli::ImportSource;
+use crate::commands::install::{self, InstallOptions};
+use crate::config::{ADDONS_DIR, Addon, Config};
+use crate::error::{Error, Result};
+use crate::git::{
+    Track, ensure_repo, expand_source, extract_repo_name, get_commit_hash, latest_rev,
+    list_remote_branches, list_submodules, remove_submodule, repo_cache_dir, rev_parse,
+    same_source, short_hash,
+};
+use crate::package::{addon_version_at, manifest_at, provided_addon_names_at};
+use crate::project::Project;
+use crate::utils::get_cache_dir;
+
+/// gd-plug script declaring the plugins of a project
+const PLUG_FILE: &str = "plug.gd";
+
+/// Directory gd-plug clones plugin repositories into
+const PLUGGED_DIR: &str = ".plugged";
+
+/// godot-package-manager manifest and lock file
+const GPM_MANIFEST: &str = "package.json";
+const GPM_LOCK: &str = "godot.lock";
+
+/// Keys of package.json holding dependencies
+const GPM_DEPENDENCY_KEYS: &[&str] = &["dependencies", "devDependencies"];
+
+/// An addon repository declared by another mechanism
+struct Declared {
+    /// How the old mechanism refers to it: submodule path, plug() argument or package name
+    origin: String,
+    source: String,
+    /// Commit, tag or branch it is locked to
+    rev: Option<String>,
+    /// Branch it follows, when `rev` is a commit checked out from it
+    branch: Option<String>,
+    /// Folder in addons/ it is installed in, if known
+    folder: Option<String>,
+}
+
+pub fn execute(project: &Project, from: ImportSource, remove_old: bool) -> Result<()> {
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let mut config = Config::load(project)?;
+    let mechanism = describe(from);
+
+    let declared = match from {
+        ImportSource::Submodules => read_submodules(project, &config)?,
+        ImportSource::GdPlug => read_gd_plug(project, &config)?,
+        ImportSource::Gpm => read_gpm(project, &config)?,
+    };
+    if declared.is_empty() {
+        status!("No addons declared by {} found.", mechanism);
+        return Ok(());
+    }
+
+    let cache_dir = get_cache_dir()?;
+    let mut imported = Vec::new();
+    let mut added = 0;
+
+    for declared in &declared {
+        status!("{} {}", "Importing".bold(), declared.origin);
+        let addons = match lock_declared(project, declared, &cache_dir, &config) {
+            Ok(addons) => addons,
+            Err(e) => {
+                status!("{}", format!("  Skipped: {}", e).yellow());
+                continue;
+            }
+        };
+
+        let mut conflict = false;
+        for addon in addons {
+            match config.addons.iter().find(|a| a.name == addon.name) {
+                Some(existing) if same_source(&existing.source, &addon.source) => {
+                    status!("  {} {}", addon.name, "(already in gdam.yaml)".dimmed());
+                }
+                Some(existing) => {
+                    status!(
+                        "{}",
+                        format!(
+                            "  Skipped: addons/{} is already installed from {}",
+                            addon.name, existing.source
+                        )
+                        .yellow()
+                    );
+                    conflict = true;
+                }
+                None => {
+                    status!(
+                        "  {} ({}) at {}",
+                        addon.name.green(),
+                        addon.version,
+                        short_hash(&addon.checksum)
+                    );
+                    config.addons.push(addon);
+                    added += 1;
+                }
+            }
+        }
+        if !conflict {
+            imported.push(declared);
+        }
+    }
+
+    config.save(project)?;
+    status!(
+        "\n{}",
+        format!("Added {} addon(s) to gdam.yaml.", added).green()
+    );
+
+    let skipped = declared.len() - imported.len();
+    if skipped > 0 {
+        status!(
+            "{}",
+            format!(
+                "{} entr{} of {} could not be imported and stay as they are.",
+                skipped,
+                if skipped == 1 { "y" } else { "ies" },
+                mechanism
+            )
+            .yellow()
+        );
+    }
+
+    if imported.is_empty() {
+        return Ok(());
+    }
+
+    if !remove_old {
+        let flag = from
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        status!(
+            "\n{}",
+            format!(
+                "The addons are still managed by {}. Run 'gdam import --from {} --remove-old'\nto remove the old setup and install them with gdam.",
+                mechanism, flag
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    status!();
+    let origins: Vec<&str> = imported.iter().map(|d| d.origin.as_str()).collect();
+    match from {
+        ImportSource::Submodules => remove_submodules(project, &origins)?,
+        ImportSource::GdPlug => remove_gd_plug(project, skipped == 0)?,
+        ImportSource::Gpm => remove_gpm_dependencies(project, &origins)?,
+    }
+
+    status!();
+    install::execute(project, None, InstallOptions::default())
+}
+
+/// Human readable name of an import source
+fn describe(from: ImportSource) -> &'static str {
+    match from {
+        ImportSource::Submodules => "git submodules",
+        ImportSource::GdPlug => "gd-plug",
+        ImportSource::Gpm => "godot-package-manager",
+    }
+}
+
+/// Fetch a declared repository and build gdam.yaml entries for the addons it
+/// provides at the declared revision
+fn lock_declared(
+    project: &Project,
+    declared: &Declared,
+    cache_dir: &Path,
+    config: &Config,
+) -> Result<Vec<Addon>> {
+    let clone_dir = repo_cache_dir(cache_dir, &declared.source)?;
+    status!("  Fetching {}...", declared.source);
+    ensure_repo(project, &declared.source, &clone_dir)?;
+
+    // Branches are followed on upgrade, tags and commits are only locked
+    let branches = list_remote_branches(&clone_dir)?;
+    let is_branch = |name: &&String| branches.contains(name);
+    let commit = match &declared.rev {
+        Some(rev) if is_branch(&rev) => rev_parse(&clone_dir, &format!("origin/{}", rev))?,
+        Some(rev) => rev_parse(&clone_dir, rev)?,
+        None => rev_parse(&clone_dir, &latest_rev(&clone_dir, None)?)?,
+    };
+    let track = declared
+        .branch
+        .iter()
+        .chain(&declared.rev)
+        .find(is_branch)
+        .map(|branch| Track::Branch(branch.clone()));
+
+    let names = provided_addon_names_at(&clone_dir, &commit)?;
+    if names.is_empty() {
+        return Err(Error::AddonMissingInRepo(format!(
+            "{} provides no addons in {}/ nor a plugin.cfg at its root at {}",
+            declared.source,
+            ADDONS_DIR,
+            short_hash(&commit)
+        )));
+    }
+
+    let manifest = manifest_at(&clone_dir, &commit);
+    let dependencies: Vec<String> = manifest
+        .as_ref()
+        .map(|m| m.dependencies.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|d| expand_source(&d.source, &config.source_schemes))
+        .collect();
+
+    // A single addon keeps the folder name it had in the project
+    let folder = declared.folder.as_ref().filter(|_| names.len() == 1);
+
+    Ok(names
+        .into_iter()
+        .map(|upstream_name| {
+            let version = addon_version_at(&clone_dir, &commit, &upstream_name)
+                .or_else(|| manifest.as_ref().and_then(|m| m.version.clone()))
+                .unwrap_or_default();
+            let name = folder.cloned().unwrap_or_else(|| upstream_name.clone());
+            Addon {
+                version,
+                source: declared.source.clone(),
+                checksum: commit.clone(),
+                track: track.as_ref().map(|t| t.to_string()),
+                transitive: false,
+                dependencies: dependencies.clone(),
+                upstream_name: (upstream_name != name).then_some(upstream_name),
+                policy: None,
+                name,
+            }
+        })
+        .collect())
+}
+
+/// Read the submodules of the project and the commits they are locked to
+fn read_submodules(project: &Project, config: &Config) -> Result<Vec<Declared>> {
+    let mut declared = Vec::new();
+    for submodule in list_submodules(project.root())? {
+        let Some(source) = expand_source(&submodule.url, &config.source_schemes) else {
+            status!(
+                "{}",
+                format!(
+                    "Skipped submodule {}: unsupported URL '{}'",
+                    submodule.path, submodule.url
+                )
+                .yellow()
+            );
+            continue;
+        };
+        if submodule.commit.is_none() {
+            status!(
+                "{}",
+                format!("Skipped submodule {}: not added to git", submodule.path).yellow()
+            );
+            continue;
+        }
+        let folder = submodule
+            .path
+            .strip_prefix(&format!("{}/", ADDONS_DIR))
+            .filter(|f| !f.contains('/'))
+            .map(String::from);
+        declared.push(Declared {
+            origin: submodule.path,
+            source,
+            rev: submodule.commit,
+            branch: None,
+            folder,
+        });
+    }
+    Ok(declared)
+}
+
+/// Read the plug() calls of gd-plug's plug.gd. The commit checked out in
+/// `.plugged/` is used unless the call pins a commit.
+fn read_gd_plug(project: &Project, config: &Config) -> Result<Vec<Declared>> {
+    let path = project.path(PLUG_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut declared = Vec::new();
+    for (repo, options) in parse_plug_calls(&fs::read_to_string(&path)?) {
+        let Some(source) = expand_source(&repo, &config.source_schemes) else {
+            status!(
+                "{}",
+                format!("Skipped plug(\"{}\"): unsupported repository", repo).yellow()
+            );
+            continue;
+        };
+
+        let plugged_dir = extract_repo_name(&source).map(|n| project.path(PLUGGED_DIR).join(n));
+        let installed = plugged_dir
+            .filter(|dir| dir.join(".git").exists())
+            .and_then(|dir| get_commit_hash(&dir).ok());
+        let (rev, branch) = plug_rev(&options, installed);
+
+        declared.push(Declared {
+            origin: repo,
+            source,
+            rev,
+            branch,
+            folder: None,
+        });
+    }
+    Ok(declared)
+}
+
+/// The revision a plug() call is locked to and the branch it follows, if any.
+/// A pinned commit wins over the commit installed in `.plugged/`, which wins
+/// over the declared tag or branch.
+fn plug_rev(options: &str, installed: Option<String>) -> (Option<String>, Option<String>) {
+    let commit = string_option(options, "commit");
+    let tag = string_option(options, "tag");
+    let branch = string_option(options, "branch").filter(|_| commit.is_none() && tag.is_none());
+    let rev = commit.or(installed).or(tag).or_else(|| branch.clone());
+    (rev, branch)
+}
+
+/// Find the `plug("repo", {...})` calls of a GDScript file, as (repo, options) pairs
+fn parse_plug_calls(content: &str) -> Vec<(String, String)> {
+    let code: Vec<&str> = content
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .collect();
+    let code = code.join("\n");
+
+    let mut calls = Vec::new();
+    let mut rest = code.as_str();
+    while let Some(i) = rest.find("plug(") {
+        let preceded_by_word = rest[..i]
+            .chars()
+            .last()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        rest = &rest[i + "plug(".len()..];
+        if preceded_by_word {
+            continue;
+        }
+
+        // Arguments run until the matching parenthesis
+        let mut depth = 0;
+        let mut quote = None;
+        let mut end = rest.len();
+        for (j, c) in rest.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '(' | '[' | '{') => depth += 1,
+                (None, ')') if depth == 0 => {
+                    end = j;
+                    break;
+                }
+                (None, ')' | ']' | '}') => depth -= 1,
+                _ => {}
+            }
+        }
+        let args = &rest[..end];
+        rest = &rest[end..];
+
+        if let Some((repo, options)) = parse_string_literal(args) {
+            calls.push((repo, options.to_string()));
+        }
+    }
+    calls
+}
+
+/// Parse a string literal at the start of a text (after whitespace), returning
+/// its value and the text after it
+fn parse_string_literal(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start();
+    let quote = text.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let end = text[1..].find(quote)? + 1;
+    Some((text[1..end].to_string(), &text[end + 1..]))
+}
+
+/// Read a string value of a GDScript dictionary literal, e.g. `"tag": "v1.0"`
+fn string_option(options: &str, key: &str) -> Option<String> {
+    ["\"", "'"].iter().find_map(|q| {
+        let quoted_key = format!("{}{}{}", q, key, q);
+        let after = &options[options.find(&quoted_key)? + quoted_key.len()..];
+        let after = after.trim_start().strip_prefix(':')?;
+        parse_string_literal(after).map(|(value, _)| value)
+    })
+}
+
+/// Read the git dependencies of godot-package-manager's package.json.
+/// Packages from the npm registry can't be traced back to a repository and are skipped.
+fn read_gpm(project: &Project, config: &Config) -> Result<Vec<Declared>> {
+    let path = project.path(GPM_MANIFEST);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| Error::json(GPM_MANIFEST, e))?;
+
+    let mut declared = Vec::new();
+    for key in GPM_DEPENDENCY_KEYS {
+        let Some(dependencies) = manifest.get(key).and_then(|d| d.as_object()) else {
+            continue;
+        };
+        for (package, spec) in dependencies {
+            let spec = spec.as_str().unwrap_or_default();
+            let Some((source, rev)) = parse_gpm_spec(spec, config) else {
+                status!(
+                    "{}",
+                    format!(
+                        "Skipped {}: '{}' is not a git dependency, use 'gdam adopt' with its repository URL",
+                        package, spec
+                    )
+                    .yellow()
+                );
+                continue;
+            };
+            let folder = package.rsplit('/').next().map(String::from);
+            declared.push(Declared {
+                origin: package.clone(),
+                source,
+                rev,
+                branch: None,
+                folder,
+            });
+        }
+    }
+    Ok(declared)
+}
+
+/// Parse an npm git dependency, e.g. `github:owner/repo#v1.0` or
+/// `git+https://host/repo.git#commit`, into a source and revision
+fn parse_gpm_spec(spec: &str, config: &Config) -> Option<(String, Option<String>)> {
+    let (url, rev) = match spec.split_once('#') {
+        Some((url, rev)) => (url, Some(rev)),
+        None => (spec, None),
+    };
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    let url = if let Some(path) = url.strip_prefix("github:") {
+        format!("gh:{}", path)
+    } else if let Some(path) = url.strip_prefix("gitlab:") {
+        format!("gl:{}", path)
+    } else {
+        url.to_string()
+    };
+
+    // Tarball URLs are not repositories
+    if url.ends_with(".tgz") || url.ends_with(".tar.gz") {
+        return None;
+    }
+
+    // `#semver:^1.0` ranges are resolved by npm, not pinned
+    let rev = rev
+        .filter(|r| !r.is_empty() && !r.starts_with("semver:"))
+        .map(String::from);
+    expand_source(&url, &config.source_schemes).map(|source| (source, rev))
+}
+
+/// Deinitialize and remove the imported submodules
+fn remove_submodules(project: &Project, paths: &[&str]) -> Result<()> {
+    for submodule in list_submodules(project.root())? {
+        if paths.contains(&submodule.path.as_str()) {
+            remove_submodule(project.root(), &submodule)?;
+            status!("Removed submodule {}", submodule.path);
+        }
+    }
+    Ok(())
+}
+
+/// Remove plug.gd and gd-plug's clones, unless some plugins were not imported
+fn remove_gd_plug(project: &Project, all_imported: bool) -> Result<()> {
+    if !all_imported {
+        status!(
+            "{}",
+            format!(
+                "Kept {} because not all of its plugins were imported",
+                PLUG_FILE
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    fs::remove_file(project.path(PLUG_FILE))?;
+    status!("Removed {}", PLUG_FILE);
+    let plugged_dir = project.path(PLUGGED_DIR);
+    if plugged_dir.exists() {
+        fs::remove_dir_all(plugged_dir)?;
+        status!("Removed {}/", PLUGGED_DIR);
+    }
+    if project.addons_dir().join("gd-plug").exists() {
+        status!(
+            "{}",
+            "Disable the gd-plug plugin in the project settings and delete addons/gd-plug to finish"
+                .yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Remove the imported packages from package.json, and godot.lock once no
+/// dependencies are left
+fn remove_gpm_dependencies(project: &Project, packages: &[&str]) -> Result<()> {
+    let path = project.path(GPM_MANIFEST);
+    let mut manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| Error::json(GPM_MANIFEST, e))?;
+
+    let mut remaining = 0;
+    for key in GPM_DEPENDENCY_KEYS {
+        if let Some(dependencies) = manifest.get_mut(key).and_then(|d| d.as_object_mut()) {
+            dependencies.retain(|package, _| !packages.contains(&package.as_str()));
+            remaining += dependencies.len();
+        }
+    }
+
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| Error::Other(format!("Failed to write {}: {}", GPM_MANIFEST, e)))?;
+    fs::write(path, content + "\n")?;
+    status!(
+        "Removed {} package(s) from {}",
+        packages.len(),
+        GPM_MANIFEST
+    );
+
+    let lock = project.path(GPM_LOCK);
+    if remaining == 0 && lock.exists() {
+        fs::remove_file(lock)?;
+        status!("Removed {}", GPM_LOCK);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plug_calls() {
+        let calls = parse_plug_calls(
+            r#"extends "res://addons/gd-plug/plug.gd"
+
+func _plugging():
+	plug("imjp94/gd-YAFSM")
+	plug('owner/tagged', {"tag": "v1.0", "exclude": ["addons/x (copy)"]})
+	plug("owner/branchy", {
+		"branch": "godot-4",
+		"commit": "0123abcd",
+	})
+	# plug("owner/commented")
+	unplug("owner/other")
+	plug(REPO)
+"#,
+        );
+        let repos: Vec<&str> = calls.iter().map(|(repo, _)| repo.as_str()).collect();
+        assert_eq!(repos, ["imjp94/gd-YAFSM", "owner/tagged", "owner/branchy"]);
+        assert_eq!(calls[0].1, "");
+        assert_eq!(string_option(&calls[1].1, "tag").as_deref(), Some("v1.0"));
+        assert_eq!(
+            string_option(&calls[2].1, "branch").as_deref(),
+            Some("godot-4")
+        );
+        assert_eq!(
+            string_option(&calls[2].1, "commit").as_deref(),
+            Some("0123abcd")
+        );
+        assert_eq!(string_option(&calls[2].1, "tag"), None);
+    }
+
+    #[test]
+    fn plug_revs() {
+        let installed = || Some("89abcdef".to_string());
+
+        // The installed commit is locked, the declared branch still followed
+        assert_eq!(
+            plug_rev(r#"{"branch": "godot-4"}"#, installed()),
+            (Some("89abcdef".to_string()), Some("godot-4".to_string()))
+        );
+        assert_eq!(
+            plug_rev(r#"{"branch": "godot-4"}"#, None),
+            (Some("godot-4".to_string()), Some("godot-4".to_string()))
+        );
+        // Pinned commits and tags are only locked
+        assert_eq!(
+            plug_rev(
+                r#"{"branch": "godot-4", "commit": "0123abcd"}"#,
+                installed()
+            ),
+            (Some("0123abcd".to_string()), None)
+        );
+        assert_eq!(
+            plug_rev(r#"{"tag": "v1.0"}"#, installed()),
+            (Some("89abcdef".to_string()), None)
+        );
+        assert_eq!(plug_rev("", None), (None, None));
+    }
+
+    #[test]
+    fn plug_calls_with_parentheses_in_strings() {
+        let calls = parse_plug_calls("plug(\"a/b\", {\"tag\": \"v1 (old)\"})\nplug(\"c/d\")\n");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(
+            string_option(&calls[0].1, "tag").as_deref(),
+            Some("v1 (old)")
+        );
+        assert_eq!(calls[1].0, "c/d");
+    }
+
+    #[test]
+    fn gpm_specs() {
+        let config = Config::default();
+        let spec = |spec| parse_gpm_spec(spec, &config);
+        assert_eq!(
+            spec("github:owner/repo#v1.0"),
+            Some((
+                "https://github.com/owner/repo".to_string(),
+                Some("v1.0".to_string())
+            ))
+        );
+        assert_eq!(
+            spec("gitlab:group/repo"),
+            Some(("https://gitlab.com/group/repo".to_string(), None))
+        );
+        assert_eq!(
+            spec("git+https://example.com/repo.git#0123abcd"),
+            Some((
+                "https://example.com/repo.git".to_string(),
+                Some("0123abcd".to_string())
+            ))
+        );
+        assert_eq!(
+            spec("owner/repo#semver:^1.0"),
+            Some(("https://github.com/owner/repo".to_string(), None))
+        );
+        assert_eq!(
+            spec("git+ssh://git@github.com/owner/repo.git#"),
+            Some(("ssh://git@github.com/owner/repo.git".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn gpm_registry_packages() {
+        let config = Config::default();
+        for spec in [
+            "^1.2.0",
+            "1.0.0",
+            "latest",
+            "https://example.com/pkg.tgz",
+            "",
+        ] {
+            assert_eq!(parse_gpm_spec(spec, &config), None, "{}", spec);
+        }
+    }
+}
@@ -1,18 +1,32 @@
-use std::path::Path;
+use std::fs;
 
 use colored::Colorize;
 
-use crate::config::{ADDONS_DIR, Config, get_existing_addons};
+use crate::commands::adopt::{Adoption, match_addon, print_adoption};
+use crate::config::{Config, get_existing_addons};
 use crate::error::Result;
-use crate::utils::{ensure_in_godot_project, has_global_addons_ignore, update_gitignore};
+use crate::git::{expand_source, get_commit_hash, remote_url, same_source, short_hash};
+use crate::gitignore;
+use crate::plugin::parse_plugin_cfg;
+use crate::project::Project;
+use crate::registry::{RegistryEntry, load_entries};
+use crate::utils::{confirm, prompt};
 
-pub fn execute() -> Result<()> {
-    // Check if in a Godot project
-    ensure_in_godot_project()?;
+/// README file names looked up in addon folders
+const README_FILES: &[&str] = &["README.md", "README", "readme.md", "Readme.md"];
 
-    // Check if gdam.yaml already exists
-    if Config::exists() {
-        println!(
+/// Forges whose repository links are recognized in addon files
+const FORGE_HOSTS: &[&str] = &["github.com", "gitlab.com", "codeberg.org"];
+
+/// A possible source of an existing addon and where it was found
+struct Clue {
+    source: String,
+    found_in: String,
+}
+
+pub fn execute(project: &Project, yes: bool, vendor: bool, registries: Vec<String>) -> Result<()> {
+    if Config::exists(project) {
+        status!(
             "{}",
             "gdam.yaml already exists, skipping initialization.".yellow()
         );
@@ -20,33 +34,239 @@ pub fn execute() -> Result<()> {
     }
 
     // Create default gdam.yaml
-    let config = Config::default();
-    config.save()?;
-    println!("Created gdam.yaml");
-
-    // If no addons folder exists, add addons/ to gitignore
-    let addons_path = Path::new(ADDONS_DIR);
-    if !addons_path.exists() && !has_global_addons_ignore() {
-        update_gitignore(&["addons/".to_string()])?;
-        println!("Added addons/ to .gitignore");
-    }
+    let mut config = Config {
+        vendor,
+        registries,
+        ..Config::default()
+    };
+    config.save(project)?;
+    status!("Created gdam.yaml");
 
     // Check for existing addons folder
-    let existing_addons = get_existing_addons()?;
+    let existing_addons = get_existing_addons(project)?;
     if !existing_addons.is_empty() {
-        println!(
+        status!(
             "\n{}",
             "Found existing addons that can be migrated to gdam:".yellow()
         );
-        for addon in &existing_addons {
-            println!("{}", format!("  - {}", addon).yellow());
+        migrate_existing_addons(project, &existing_addons, &mut config, yes)?;
+    }
+
+    status!("Initialization complete!");
+    Ok(())
+}
+
+/// Find the source and version of each existing addon and adopt the confirmed ones
+fn migrate_existing_addons(
+    project: &Project,
+    names: &[String],
+    config: &mut Config,
+    yes: bool,
+) -> Result<()> {
+    let registries = config.effective_registries();
+    let registry_entries = if registries.is_empty() {
+        Vec::new()
+    } else {
+        load_entries(project, &registries).unwrap_or_else(|e| {
+            status!("{}", format!("  Warning: {}", e).yellow());
+            Vec::new()
+        })
+    };
+
+    let mut adopted = Vec::new();
+    let mut skipped = Vec::new();
+
+    for name in names {
+        status!("\n{}", format!("addons/{}", name).bold());
+
+        let mut adoption = None;
+        for clue in detect_sources(project, name, &registry_entries, config) {
+            status!("  Found {} in {}", clue.source, clue.found_in);
+            match match_addon(project, name, &clue.source, config) {
+                Ok(found) => {
+                    adoption = Some(found);
+                    break;
+                }
+                Err(e) => status!("{}", format!("  {}", e).yellow()),
+            }
         }
-        println!(
+
+        if adoption.is_none() && !yes {
+            adoption = ask_source(project, name, config)?;
+        }
+
+        let Some(adoption) = adoption else {
+            status!("  {}", "No source found".dimmed());
+            skipped.push(name.clone());
+            continue;
+        };
+
+        print_adoption(&adoption, "  ");
+        // Without a question, only adopt addons whose files match a commit exactly
+        let accept = if yes {
+            if !adoption.diff.is_empty() {
+                status!(
+                    "{}",
+                    "  Skipped: files differ from upstream, adopt it with 'gdam adopt' after checking them"
+                        .yellow()
+                );
+            }
+            adoption.diff.is_empty()
+        } else {
+            confirm(&format!("  Adopt {} from {}?", name, adoption.addon.source))?
+        };
+        if accept {
+            config.addons.push(adoption.addon);
+            adopted.push(name.clone());
+        } else {
+            skipped.push(name.clone());
+        }
+    }
+
+    config.save(project)?;
+    gitignore::sync(project, config)?;
+
+    status!();
+    if !adopted.is_empty() {
+        status!(
+            "{}",
+            format!("Adopted {} addon(s) into gdam.yaml.", adopted.len()).green()
+        );
+    }
+    if !skipped.is_empty() {
+        status!("{}", "Not managed by gdam:".yellow());
+        for name in &skipped {
+            status!("{}", format!("  - {}", name).yellow());
+        }
+        status!(
             "\n{}",
-            "Use 'gdam install' to add these addons to gdam management.".yellow()
+            "Use 'gdam adopt <addon> <git-url>' to add these addons to gdam management\nwithout changing their version."
+                .yellow()
         );
     }
-
-    println!("Initialization complete!");
     Ok(())
 }
+
+/// Ask the user for the source of an addon no source was found for
+fn ask_source(project: &Project, name: &str, config: &Config) -> Result<Option<Adoption>> {
+    loop {
+        let answer = prompt(&format!("  Git URL of {} (empty to skip):", name))?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        let Some(source) = expand_source(&answer, &config.source_schemes) else {
+            status!("{}", format!("  Invalid URL: {}", answer).yellow());
+            continue;
+        };
+        match match_addon(project, name, &source, config) {
+            Ok(adoption) => return Ok(Some(adoption)),
+            Err(e) => status!("{}", format!("  {}", e).yellow()),
+        }
+    }
+}
+
+/// Look for the source of an addon folder: a nested git clone, registry
+/// entries, and forge links in plugin.cfg and the README
+fn detect_sources(
+    project: &Project,
+    name: &str,
+    registry_entries: &[RegistryEntry],
+    config: &Config,
+) -> Vec<Clue> {
+    let dir = project.addons_dir().join(name);
+    let mut clues = Vec::new();
+
+    if dir.join(".git").exists()
+        && let Some(url) = remote_url(&dir)
+    {
+        let found_in = match get_commit_hash(&dir) {
+            Ok(head) => format!("nested git repository (HEAD {})", short_hash(&head)),
+            Err(_) => "nested git repository".to_string(),
+        };
+        clues.push(Clue {
+            source: url,
+            found_in,
+        });
+    }
+
+    let plugin_cfg = dir.join("plugin.cfg");
+    let plugin_name = parse_plugin_cfg(&plugin_cfg).name;
+    for entry in registry_entries {
+        if (entry.name.eq_ignore_ascii_case(name)
+            || plugin_name
+                .as_ref()
+                .is_some_and(|n| entry.name.eq_ignore_ascii_case(n)))
+            && let Some(source) = expand_source(&entry.source, &config.source_schemes)
+        {
+            clues.push(Clue {
+                source,
+                found_in: "registry".to_string(),
+            });
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(&plugin_cfg) {
+        for source in find_forge_urls(&content) {
+            clues.push(Clue {
+                source,
+                found_in: "plugin.cfg".to_string(),
+            });
+        }
+    }
+
+    for file in README_FILES {
+        if let Ok(content) = fs::read_to_string(dir.join(file)) {
+            for source in find_forge_urls(&content) {
+                clues.push(Clue {
+                    source,
+                    found_in: file.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut unique: Vec<Clue> = Vec::new();
+    for clue in clues {
+        if !unique.iter().any(|c| same_source(&c.source, &clue.source)) {
+            unique.push(clue);
+        }
+    }
+    unique
+}
+
+/// Find repository links to known forges in a text, e.g. `https://github.com/owner/repo`
+fn find_forge_urls(text: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    for host in FORGE_HOSTS {
+        let marker = format!("{}/", host);
+        for (start, _) in text.match_indices(&marker) {
+            let rest = &text[start + marker.len()..];
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')))
+                .unwrap_or(rest.len());
+
+            // GitLab puts sub-pages after a `-` segment and allows nested groups
+            let segments: Vec<&str> = rest[..end]
+                .split('/')
+                .take_while(|s| !s.is_empty() && *s != "-")
+                .collect();
+            let segments = if *host == "gitlab.com" {
+                &segments[..]
+            } else {
+                &segments[..segments.len().min(2)]
+            };
+            if segments.len() < 2 || segments[0] == "godotengine" {
+                continue;
+            }
+
+            let path = segments.join("/");
+            let path = path.trim_end_matches('.');
+            let path = path.strip_suffix(".git").unwrap_or(path);
+            let url = format!("https://{}/{}", host, path);
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
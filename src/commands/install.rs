@@ -1,116 +1,612 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
 
 use colored::Colorize;
 
-use crate::config::{ADDONS_DIR, Addon, Config};
+use crate::config::{Addon, Config, UpgradePolicy};
 use crate::error::{Error, Result};
-use crate::git::{checkout, ensure_repo, extract_repo_name, get_commit_hash, is_valid_git_url};
-use crate::plugin::parse_plugin_cfg;
-use crate::utils::{
-    copy_dir_recursive, ensure_in_godot_project, get_cache_dir, has_global_addons_ignore,
-    update_gitignore,
+use crate::git::{
+    Track, checkout, checkout_latest, ensure_repo, ensure_sources, expand_source, get_commit_hash,
+    is_ancestor, is_valid_git_url, list_remote_branches, list_tags, redact, repo_cache_dir,
+    resolve_engine_track, rev_parse, same_source, short_hash, tag_series,
 };
+use crate::gitignore;
+use crate::history::{Change, History};
+use crate::output;
+use crate::package::{PackageManifest, copy_addon, find_provided_addon, provided_addons};
+use crate::plugin::parse_plugin_cfg;
+use crate::project::Project;
+use crate::registry;
+use crate::utils::{get_cache_dir, get_godot_version, register_autoloads};
+use crate::version::VersionReq;
+
+/// Options for installing from a git URL
+#[derive(Default)]
+pub struct InstallOptions {
+    /// Branch, tag or commit to install
+    pub git_ref: Option<String>,
+    /// Run post-install hooks declared by packages
+    pub run_hooks: bool,
+    /// Replace addons of the same name installed from another source
+    pub force: bool,
+    /// Install the addon under another folder name
+    pub alias: Option<String>,
+    /// Upgrade policy of the installed addons
+    pub policy: Option<UpgradePolicy>,
+}
 
-pub fn execute(git_url: Option<String>) -> Result<()> {
-    ensure_in_godot_project()?;
-
+pub fn execute(project: &Project, git_url: Option<String>, options: InstallOptions) -> Result<()> {
     match git_url {
-        Some(url) => install_from_url(&url),
-        None => install_all_dependencies(),
+        Some(url) => install_from_url(project, &url, &options),
+        None => install_all_dependencies(project),
     }
 }
 
-/// Install addon from a git URL
-fn install_from_url(url: &str) -> Result<()> {
-    if !is_valid_git_url(url) {
-        return Err(Error::InvalidUrl(url.to_string()));
+/// Checkout the requested ref, or the line matching the project's Godot version.
+/// Returns the line to follow on upgrade.
+fn checkout_install_ref(
+    project: &Project,
+    clone_dir: &Path,
+    git_ref: Option<&str>,
+) -> Result<Option<Track>> {
+    if let Some(git_ref) = git_ref {
+        if list_remote_branches(clone_dir)?
+            .iter()
+            .any(|b| b == git_ref)
+        {
+            let track = Track::Branch(git_ref.to_string());
+            checkout_latest(clone_dir, Some(&track))?;
+            return Ok(Some(track));
+        }
+        checkout(clone_dir, git_ref)?;
+        // A version tag follows its series, other tags and commits are pinned
+        let series = tag_series(git_ref).filter(|_| {
+            list_tags(clone_dir, Some(git_ref)).is_ok_and(|tags| tags.iter().any(|t| t == git_ref))
+        });
+        if let Some(pattern) = series {
+            status!("Following '{}' on upgrade", pattern);
+            return Ok(Some(Track::Tags(pattern)));
+        }
+        return Ok(None);
     }
 
-    let repo_name = extract_repo_name(url).ok_or_else(|| Error::InvalidUrl(url.to_string()))?;
+    let track = match get_godot_version(project).map(|v| v.major) {
+        Some(engine_major) => {
+            let track = resolve_engine_track(clone_dir, engine_major)?;
+            if let Some(track) = &track {
+                status!("Following '{}' for Godot {}", track, engine_major);
+            }
+            track
+        }
+        None => None,
+    };
 
-    println!("Installing addon from: {}", url);
+    checkout_latest(clone_dir, track.as_ref())?;
+    Ok(track)
+}
 
-    // Ensure repository in cache directory
-    let cache_dir = get_cache_dir()?;
-    let clone_dir = cache_dir.join(&repo_name);
+/// A repository resolved as part of the dependency graph
+struct ResolvedSource {
+    source: String,
+    /// Ref requested for this source, if any
+    git_ref: Option<String>,
+    /// Source that required this one (None for the requested addon)
+    required_by: Option<String>,
+    clone_dir: PathBuf,
+    commit: String,
+    track: Option<Track>,
+    /// Installed at a commit or tag that is not part of a version series
+    pinned: bool,
+    manifest: Option<PackageManifest>,
+    dependencies: Vec<String>,
+}
 
-    println!("Fetching repository...");
-    ensure_repo(url, &clone_dir)?;
+/// Resolve a source and, recursively, its dependencies.
+/// Dependencies are pushed to `resolved` before their dependents.
+fn resolve_source(
+    project: &Project,
+    source: &str,
+    git_ref: Option<&str>,
+    required_by: Option<&str>,
+    config: &Config,
+    stack: &mut Vec<String>,
+    resolved: &mut Vec<ResolvedSource>,
+) -> Result<()> {
+    if let Some(pos) = stack.iter().position(|s| same_source(s, source)) {
+        let mut cycle = stack[pos..].to_vec();
+        cycle.push(source.to_string());
+        return Err(Error::Other(format!(
+            "Dependency cycle detected: {}",
+            cycle.join(" -> ")
+        )));
+    }
+
+    if let Some(index) = resolved.iter().position(|r| same_source(&r.source, source)) {
+        let existing = &resolved[index];
+        match (existing.git_ref.as_deref(), git_ref) {
+            (Some(a), Some(b)) if a != b => {
+                return Err(Error::Other(format!(
+                    "Conflicting requirements for {}:\n  {} requires '{}'\n  {} requires '{}'",
+                    source,
+                    existing.required_by.as_deref().unwrap_or("gdam.yaml"),
+                    a,
+                    required_by.unwrap_or("gdam.yaml"),
+                    b
+                )));
+            }
+            // Previously resolved without a ref, resolve again at the required one
+            (None, Some(_)) => {
+                resolved.remove(index);
+            }
+            _ => return Ok(()),
+        }
+    }
+
+    // Dependencies already in the project keep their locked version,
+    // as long as it satisfies the ref the dependent requires
+    if let Some(parent) = required_by
+        && let Some(installed) = config
+            .addons
+            .iter()
+            .find(|a| same_source(&a.source, source))
+    {
+        if let Some(git_ref) = git_ref {
+            check_locked_ref(project, installed, git_ref, parent)?;
+        }
+        return Ok(());
+    }
+
+    if !is_valid_git_url(source) {
+        return Err(Error::InvalidUrl(source.to_string()));
+    }
+    let clone_dir = repo_cache_dir(&get_cache_dir()?, source)?;
+
+    match required_by {
+        Some(parent) => status!("Fetching dependency {} (required by {})...", source, parent),
+        None => status!("Fetching repository..."),
+    }
+
+    // Ensure repository in cache directory
+    ensure_repo(project, source, &clone_dir)?;
+    let track = checkout_install_ref(project, &clone_dir, git_ref)?;
 
     // Get commit hash before processing
-    let commit_hash = get_commit_hash(&clone_dir)?;
+    let commit = get_commit_hash(&clone_dir)?;
+
+    // Check the repository provides addons
+    let manifest = PackageManifest::load(&clone_dir)?;
+    if provided_addons(&clone_dir, manifest.as_ref())?.is_empty() {
+        return Err(Error::AddonMissingInRepo(format!(
+            "No 'addons' directory or root plugin.cfg found in repository {}",
+            source
+        )));
+    }
+
+    warn_if_incompatible(
+        project,
+        source,
+        manifest.as_ref().and_then(|m| m.godot.as_deref()),
+    );
+
+    // Dependencies may use shorthand sources, store them as URLs
+    let mut dependencies = manifest
+        .as_ref()
+        .map(|m| m.dependencies.clone())
+        .unwrap_or_default();
+    for dep in &mut dependencies {
+        dep.source = expand_source(&dep.source, &config.source_schemes)
+            .ok_or_else(|| Error::InvalidUrl(dep.source.clone()))?;
+    }
+
+    stack.push(source.to_string());
+    for dep in &dependencies {
+        resolve_source(
+            project,
+            &dep.source,
+            dep.git_ref.as_deref(),
+            Some(source),
+            config,
+            stack,
+            resolved,
+        )?;
+    }
+    stack.pop();
+
+    resolved.push(ResolvedSource {
+        source: source.to_string(),
+        git_ref: git_ref.map(String::from),
+        required_by: required_by.map(String::from),
+        clone_dir,
+        commit,
+        pinned: git_ref.is_some() && track.is_none(),
+        track,
+        manifest,
+        dependencies: dependencies.into_iter().map(|d| d.source).collect(),
+    });
 
-    // Check for addons directory
-    let source_addons_dir = clone_dir.join("addons");
-    if !source_addons_dir.exists() {
-        return Err(Error::AddonNotFound(
-            "No 'addons' directory found in repository".to_string(),
-        ));
+    Ok(())
+}
+
+/// Make sure an addon locked in gdam.yaml satisfies the ref a dependent requires:
+/// the same commit or tag, or a commit of the required branch
+fn check_locked_ref(
+    project: &Project,
+    installed: &Addon,
+    git_ref: &str,
+    required_by: &str,
+) -> Result<()> {
+    // Short names like `dead` can also be branches or tags, resolve those below
+    let abbreviated_commit = git_ref.len() >= 7 && git_ref.chars().all(|c| c.is_ascii_hexdigit());
+    if installed.track.as_deref() == Some(git_ref)
+        || (abbreviated_commit && installed.checksum.starts_with(git_ref))
+    {
+        return Ok(());
     }
 
-    // Load or create config
-    let mut config = if Config::exists() {
-        Config::load()?
+    let clone_dir = repo_cache_dir(&get_cache_dir()?, &installed.source)?;
+    ensure_repo(project, &installed.source, &clone_dir)?;
+
+    let satisfied = if list_remote_branches(&clone_dir)?
+        .iter()
+        .any(|b| b == git_ref)
+    {
+        is_ancestor(
+            &clone_dir,
+            &installed.checksum,
+            &format!("origin/{}", git_ref),
+        )
     } else {
-        return Err(Error::ConfigNotFound);
+        rev_parse(&clone_dir, git_ref)? == installed.checksum
     };
+    if satisfied {
+        return Ok(());
+    }
 
-    // Create local addons directory if needed
-    let local_addons_dir = Path::new(ADDONS_DIR);
-    fs::create_dir_all(local_addons_dir)?;
+    let commit = short_hash(&installed.checksum);
+    let locked = match &installed.track {
+        Some(track) => format!("'{}' at {}", track, commit),
+        None => commit.to_string(),
+    };
+    Err(Error::Other(format!(
+        "Conflicting requirements for {}:\n  gdam.yaml locks {} ({})\n  {} requires '{}'\nUpgrade or reinstall '{}' at a matching ref first",
+        installed.source, installed.name, locked, required_by, git_ref, installed.name
+    )))
+}
 
-    // Process each addon in the repository
-    let mut gitignore_entries = Vec::new();
-    let mut installed_count = 0;
+/// Warn if a package declares it does not support the project's Godot version
+fn warn_if_incompatible(project: &Project, source: &str, requirement: Option<&str>) {
+    let Some(requirement) = requirement else {
+        return;
+    };
+    let Some(project_version) = get_godot_version(project) else {
+        return;
+    };
 
-    for entry in fs::read_dir(&source_addons_dir)? {
-        let entry = entry?;
-        if !entry.file_type()?.is_dir() {
+    match VersionReq::parse(requirement) {
+        Some(req) if !req.matches(&project_version) => status!(
+            "{}",
+            format!(
+                "  Warning: {} supports Godot {}, but this project uses {}",
+                source, requirement, project_version
+            )
+            .yellow()
+        ),
+        Some(_) => {}
+        None => status!(
+            "{}",
+            format!(
+                "  Warning: {} declares an invalid Godot version '{}'",
+                source, requirement
+            )
+            .yellow()
+        ),
+    }
+}
+
+/// Run a package's post-install hooks in the project directory
+fn run_post_install_hooks(
+    project: &Project,
+    source: &str,
+    manifest: Option<&PackageManifest>,
+    run_hooks: bool,
+) -> Result<()> {
+    let Some(manifest) = manifest else {
+        return Ok(());
+    };
+
+    for hook in &manifest.hooks.post_install {
+        if !run_hooks {
+            status!(
+                "{}",
+                format!(
+                    "  Skipped post-install hook of {}: {} (use --run-hooks to run it)",
+                    source, hook
+                )
+                .yellow()
+            );
             continue;
         }
 
-        let addon_name = entry.file_name().to_string_lossy().to_string();
-        let src_addon_path = entry.path();
-        let dst_addon_path = local_addons_dir.join(&addon_name);
+        status!("  Running hook: {}", hook);
+        let status = if cfg!(windows) {
+            Command::new("cmd")
+                .args(["/C", hook])
+                .current_dir(project.root())
+                .stdout(output::child_stdout())
+                .status()?
+        } else {
+            Command::new("sh")
+                .args(["-c", hook])
+                .current_dir(project.root())
+                .stdout(output::child_stdout())
+                .status()?
+        };
+        if !status.success() {
+            return Err(Error::Other(format!(
+                "Post-install hook of {} failed: {}",
+                source, hook
+            )));
+        }
+    }
 
-        println!("  Installing: {}", addon_name.green());
+    Ok(())
+}
 
-        // Copy addon to local addons directory
-        if dst_addon_path.exists() {
-            fs::remove_dir_all(&dst_addon_path)?;
+/// Make sure the addons about to be installed do not replace addons of the
+/// same name from other sources, unless forced
+fn check_name_conflicts(
+    resolved: &[ResolvedSource],
+    config: &Config,
+    options: &InstallOptions,
+) -> Result<()> {
+    let mut planned: Vec<(String, &str)> = Vec::new(); // (addon name, source)
+
+    for node in resolved {
+        checkout(&node.clone_dir, &node.commit)?;
+        let provided = provided_addons(&node.clone_dir, node.manifest.as_ref())?;
+        let transitive = node.required_by.is_some();
+
+        let names: Vec<String> = match &options.alias {
+            Some(alias) if !transitive => {
+                if provided.len() != 1 {
+                    return Err(Error::Other(format!(
+                        "--as can only be used with repositories providing a single addon, {} provides {}",
+                        node.source,
+                        provided.len()
+                    )));
+                }
+                vec![alias.clone()]
+            }
+            _ => provided.into_iter().map(|a| a.name).collect(),
+        };
+
+        for name in names {
+            if let Some((_, other)) = planned
+                .iter()
+                .find(|(n, s)| *n == name && !same_source(s, &node.source))
+            {
+                return Err(Error::Other(format!(
+                    "Addon '{}' is provided by both:\n  {}\n  {}",
+                    name, other, node.source
+                )));
+            }
+
+            if !options.force
+                && let Some(existing) = config
+                    .addons
+                    .iter()
+                    .find(|a| a.name == name && !same_source(&a.source, &node.source))
+            {
+                return Err(Error::Other(format!(
+                    "Addon '{}' is already installed from another source:\n  installed: {}\n  new:       {}\nUse --force to replace it, or --as <name> to install under another name",
+                    name, existing.source, node.source
+                )));
+            }
+
+            planned.push((name, &node.source));
         }
-        copy_dir_recursive(&src_addon_path, &dst_addon_path)?;
-
-        // Parse plugin.cfg for version
-        let plugin_cfg_path = dst_addon_path.join("plugin.cfg");
-        let plugin_info = parse_plugin_cfg(&plugin_cfg_path);
-        let version = plugin_info.version.unwrap_or_default();
-
-        // Add to gitignore entries
-        gitignore_entries.push(format!("addons/{}", addon_name));
-
-        // Update config - remove existing entry if present
-        config.addons.retain(|a| a.name != addon_name);
-        config.addons.push(Addon {
-            name: addon_name,
-            version,
-            source: url.to_string(),
-            checksum: commit_hash.clone(),
-        });
+    }
+
+    Ok(())
+}
+
+/// An alias becomes a folder under addons/, so it must be a single plain path segment
+fn check_alias(alias: &str) -> Result<()> {
+    let mut components = Path::new(alias).components();
+    let plain = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !alias.contains(['/', '\\']);
+    if !plain {
+        return Err(Error::Other(format!(
+            "Invalid --as name '{}': use a plain folder name",
+            alias
+        )));
+    }
+    Ok(())
+}
+
+/// Look up a short addon name in the configured registries
+fn resolve_short_name(project: &Project, name: &str, config: &Config) -> Result<String> {
+    let registries = config.effective_registries();
+    if registries.is_empty() {
+        return Err(Error::InvalidUrl(name.to_string()));
+    }
+
+    let entry = registry::resolve(project, &registries, name)?.ok_or_else(|| {
+        Error::AddonNotFound(format!(
+            "'{}' is neither a git URL nor listed in the configured registries",
+            name
+        ))
+    })?;
+    let source = expand_source(&entry.source, &config.source_schemes)
+        .ok_or_else(|| Error::InvalidUrl(entry.source.clone()))?;
+    status!("Resolved '{}' to {}", entry.name, source);
+    warn_if_incompatible(project, &source, entry.godot.as_deref());
+    Ok(source)
+}
+
+/// Install addon from a git URL or a registry name, along with its dependencies
+fn install_from_url(project: &Project, url: &str, options: &InstallOptions) -> Result<()> {
+    // Load config
+    let mut config = if Config::exists(project) {
+        Config::load(project)?
+    } else {
+        return Err(Error::ConfigNotFound);
+    };
+
+    let source = match expand_source(url, &config.source_schemes) {
+        Some(source) => source,
+        None => resolve_short_name(project, url, &config)?,
+    };
+
+    if let Some(alias) = &options.alias {
+        check_alias(alias)?;
+    }
+
+    status!("Installing addon from: {}", redact(&source));
 
-        installed_count += 1;
+    let mut resolved = Vec::new();
+    resolve_source(
+        project,
+        &source,
+        options.git_ref.as_deref(),
+        None,
+        &config,
+        &mut Vec::new(),
+        &mut resolved,
+    )?;
+
+    check_name_conflicts(&resolved, &config, options)?;
+
+    // Create local addons directory if needed
+    let local_addons_dir = project.addons_dir();
+    fs::create_dir_all(&local_addons_dir)?;
+
+    // Process each addon in the resolved repositories
+    let mut installed_count = 0;
+    let mut changes = Vec::new();
+
+    for node in &resolved {
+        let transitive = node.required_by.is_some();
+        checkout(&node.clone_dir, &node.commit)?;
+        let manifest = node.manifest.as_ref();
+
+        for provided in provided_addons(&node.clone_dir, manifest)? {
+            let alias = options.alias.clone().filter(|_| !transitive);
+            let addon_name = alias.clone().unwrap_or_else(|| provided.name.clone());
+            let dst_addon_path = local_addons_dir.join(&addon_name);
+
+            if transitive {
+                status!(
+                    "  Installing: {} {}",
+                    addon_name.green(),
+                    "(dependency)".dimmed()
+                );
+            } else if alias.is_some() {
+                status!(
+                    "  Installing: {} {}",
+                    addon_name.green(),
+                    format!("(as alias of {})", provided.name).dimmed()
+                );
+            } else {
+                status!("  Installing: {}", addon_name.green());
+            }
+
+            // Copy addon to local addons directory
+            copy_addon(&provided.path, &dst_addon_path, manifest)?;
+
+            // Parse plugin.cfg for version, falling back to the package version
+            let plugin_cfg_path = dst_addon_path.join("plugin.cfg");
+            let plugin_info = parse_plugin_cfg(&plugin_cfg_path);
+            let version = plugin_info
+                .version
+                .or_else(|| manifest.and_then(|m| m.version.clone()))
+                .unwrap_or_default();
+
+            // Keep the upgrade policy of a reinstalled addon unless a new one is given.
+            // An addon installed at a fixed commit or tag is pinned.
+            let policy = if transitive {
+                None
+            } else if options.policy.is_none() && node.pinned {
+                status!(
+                    "  Pinned {} to {}, reinstall it with --policy <policy> to follow new releases",
+                    addon_name,
+                    options.git_ref.as_deref().unwrap_or_default()
+                );
+                Some(UpgradePolicy::Pinned)
+            } else {
+                options.policy.or_else(|| {
+                    config
+                        .addons
+                        .iter()
+                        .find(|a| a.name == addon_name)
+                        .and_then(|a| a.policy)
+                })
+            };
+
+            // Remember the replaced entry for rollback
+            let previous = config.addons.iter().find(|a| a.name == addon_name).cloned();
+            if previous.as_ref().is_none_or(|p| p.checksum != node.commit) {
+                changes.push(Change {
+                    name: addon_name.clone(),
+                    previous,
+                });
+            }
+
+            // Update config - remove existing entry if present
+            config.addons.retain(|a| a.name != addon_name);
+            config.addons.push(Addon {
+                name: addon_name,
+                version,
+                source: node.source.clone(),
+                checksum: node.commit.clone(),
+                track: node.track.as_ref().map(|t| t.to_string()),
+                transitive,
+                dependencies: node.dependencies.clone(),
+                upstream_name: alias.map(|_| provided.name),
+                policy,
+            });
+
+            installed_count += 1;
+        }
+
+        if let Some(manifest) = manifest {
+            let renames: Vec<(&str, &str)> = config
+                .addons
+                .iter()
+                .filter(|a| same_source(&a.source, &node.source))
+                .filter_map(|a| Some((a.upstream_name.as_deref()?, a.name.as_str())))
+                .collect();
+            register_package_autoloads(project, manifest, &renames)?;
+        }
+        run_post_install_hooks(project, &node.source, manifest, options.run_hooks)?;
     }
 
-    // Update .gitignore (skip if global addons ignore exists)
-    if !gitignore_entries.is_empty() && !has_global_addons_ignore() {
-        update_gitignore(&gitignore_entries)?;
+    // Addons from a reinstalled source that it no longer provides stay on their old commit
+    for node in &resolved {
+        for addon in config.addons.iter().filter(|a| {
+            same_source(&a.source, &node.source)
+                && a.checksum != node.commit
+                && a.upstream_name.is_none()
+        }) {
+            status!(
+                "{}",
+                format!(
+                    "  Warning: addon '{}' no longer exists in {}, run 'gdam uninstall {}' to remove it",
+                    addon.name, node.source, addon.name
+                )
+                .yellow()
+            );
+        }
     }
 
     // Save config
-    config.save()?;
+    config.save(project)?;
+    gitignore::sync(project, &config)?;
+    History::record(project, "install", changes)?;
 
-    println!(
+    status!(
         "{}",
         format!("Installed {} addon(s) successfully!", installed_count).green()
     );
@@ -119,63 +615,120 @@ fn install_from_url(url: &str) -> Result<()> {
 }
 
 /// Install all dependencies from gdam.yaml
-fn install_all_dependencies() -> Result<()> {
-    if !Config::exists() {
+fn install_all_dependencies(project: &Project) -> Result<()> {
+    if !Config::exists(project) {
         return Err(Error::ConfigNotFound);
     }
 
-    let config = Config::load()?;
+    let config = Config::load(project)?;
 
     if config.addons.is_empty() {
-        println!("No addons to install.");
+        status!("No addons to install.");
         return Ok(());
     }
 
-    println!("Installing {} addon(s)...", config.addons.len());
-
-    let cache_dir = get_cache_dir()?;
-    let local_addons_dir = Path::new(ADDONS_DIR);
-    fs::create_dir_all(local_addons_dir)?;
-
-    let mut gitignore_entries = Vec::new();
-
-    for addon in &config.addons {
-        println!("  Installing: {} ({})", addon.name.green(), addon.version);
-
-        let repo_name = extract_repo_name(&addon.source)
-            .ok_or_else(|| Error::InvalidUrl(addon.source.clone()))?;
-
-        let clone_dir = cache_dir.join(&repo_name);
+    // Two entries must not map to the same addons/ directory
+    for (i, addon) in config.addons.iter().enumerate() {
+        if let Some(other) = config.addons[..i].iter().find(|a| a.name == addon.name) {
+            return Err(Error::Other(format!(
+                "gdam.yaml has two entries for addons/{}:\n  {}\n  {}",
+                addon.name, other.source, addon.source
+            )));
+        }
+    }
 
-        // Ensure repo exists and checkout specific commit
-        ensure_repo(&addon.source, &clone_dir)?;
-        checkout(&clone_dir, &addon.checksum)?;
+    status!("Installing {} addon(s)...", config.addons.len());
 
-        // Copy addon from cache
-        let src_addon_path = clone_dir.join("addons").join(&addon.name);
-        if !src_addon_path.exists() {
-            println!(
+    let cache_dir = get_cache_dir()?;
+    let local_addons_dir = project.addons_dir();
+    fs::create_dir_all(&local_addons_dir)?;
+
+    // Fetch each source once and install its addons together
+    ensure_sources(
+        project,
+        config.addons.iter().map(|a| a.source.as_str()),
+        &cache_dir,
+    )?;
+    for group in config.addons_by_source() {
+        let source = &group[0].source;
+        let clone_dir = repo_cache_dir(&cache_dir, source)?;
+
+        if group.iter().any(|a| a.checksum != group[0].checksum) {
+            status!(
                 "{}",
-                format!("  Warning: addon '{}' not found in cache", addon.name).yellow()
+                format!(
+                    "  Warning: addons from {} are locked to different commits, run 'gdam upgrade {}' to align them",
+                    source, group[0].name
+                )
+                .yellow()
             );
-            continue;
         }
 
-        let dst_addon_path = local_addons_dir.join(&addon.name);
-        if dst_addon_path.exists() {
-            fs::remove_dir_all(&dst_addon_path)?;
+        let mut checked_out: Option<&str> = None;
+        for addon in &group {
+            status!("  Installing: {} ({})", addon.name.green(), addon.version);
+
+            // Checkout the locked commit
+            if checked_out != Some(addon.checksum.as_str()) {
+                checkout(&clone_dir, &addon.checksum)?;
+                checked_out = Some(&addon.checksum);
+            }
+
+            // Copy addon from cache
+            let Some(provided) = find_provided_addon(&clone_dir, addon.upstream_name())? else {
+                status!(
+                    "{}",
+                    format!("  Warning: addon '{}' not found in cache", addon.name).yellow()
+                );
+                continue;
+            };
+
+            let manifest = PackageManifest::load(&clone_dir)?;
+            let dst_addon_path = local_addons_dir.join(&addon.name);
+            copy_addon(&provided.path, &dst_addon_path, manifest.as_ref())?;
         }
-        copy_dir_recursive(&src_addon_path, &dst_addon_path)?;
 
-        gitignore_entries.push(format!("addons/{}", addon.name));
+        if let Some(manifest) = PackageManifest::load(&clone_dir)? {
+            let renames: Vec<(&str, &str)> = group
+                .iter()
+                .filter_map(|a| Some((a.upstream_name.as_deref()?, a.name.as_str())))
+                .collect();
+            register_package_autoloads(project, &manifest, &renames)?;
+        }
     }
 
-    // Update .gitignore (skip if global addons ignore exists)
-    if !gitignore_entries.is_empty() && !has_global_addons_ignore() {
-        update_gitignore(&gitignore_entries)?;
-    }
+    gitignore::sync(project, &config)?;
+
+    status!("{}", "All addons installed successfully!".green());
 
-    println!("{}", "All addons installed successfully!".green());
+    Ok(())
+}
 
+/// Register the autoload singletons declared by a package. Paths into addons
+/// installed under an alias are rewritten, `renames` holds (upstream name, alias) pairs.
+fn register_package_autoloads(
+    project: &Project,
+    manifest: &PackageManifest,
+    renames: &[(&str, &str)],
+) -> Result<()> {
+    let autoloads: Vec<(String, String)> = manifest
+        .autoloads
+        .iter()
+        .map(|a| {
+            let path = renames
+                .iter()
+                .find_map(|(upstream, alias)| {
+                    a.path
+                        .strip_prefix(&format!("res://addons/{}/", upstream))
+                        .map(|rest| format!("res://addons/{}/{}", alias, rest))
+                })
+                .unwrap_or_else(|| a.path.clone());
+            (a.name.clone(), path)
+        })
+        .collect();
+    register_autoloads(project, &autoloads)?;
+    for (name, _) in &autoloads {
+        status!("  Registered autoload: {}", name);
+    }
     Ok(())
 }
@@ -1,24 +1,36 @@
 use colored::Colorize;
+use serde::Serialize;
 
 use crate::config::Config;
 use crate::error::{Error, Result};
-use crate::utils::ensure_in_godot_project;
+use crate::git::short_hash;
+use crate::output::{self, AddonInfo};
+use crate::project::Project;
 
-pub fn execute() -> Result<()> {
-    ensure_in_godot_project()?;
+#[derive(Serialize)]
+struct ListOutput {
+    addons: Vec<AddonInfo>,
+}
 
-    if !Config::exists() {
+pub fn execute(project: &Project) -> Result<()> {
+    if !Config::exists(project) {
         return Err(Error::ConfigNotFound);
     }
 
-    let config = Config::load()?;
+    let config = Config::load(project)?;
+
+    if output::is_json() {
+        return output::print_json(&ListOutput {
+            addons: config.addons.iter().map(AddonInfo::from).collect(),
+        });
+    }
 
     if config.addons.is_empty() {
-        println!("No addons installed.");
+        status!("No addons installed.");
         return Ok(());
     }
 
-    println!("Installed addons:\n");
+    status!("Installed addons:\n");
 
     for addon in &config.addons {
         let version_display = if addon.version.is_empty() {
@@ -27,16 +39,19 @@ pub fn execute() -> Result<()> {
             addon.version.clone()
         };
 
-        println!(
-            "  {} {} {}",
+        let kind = if addon.transitive { " dependency" } else { "" };
+
+        status!(
+            "  {} {} {}{}",
             addon.name.green(),
             format!("v{}", version_display).dimmed(),
-            format!("({})", &addon.checksum[..8]).dimmed()
+            format!("({})", short_hash(&addon.checksum)).dimmed(),
+            kind.dimmed()
         );
-        println!("    {}", addon.source.dimmed());
+        status!("    {}", addon.source.dimmed());
     }
 
-    println!("\n{} addon(s) total.", config.addons.len());
+    status!("\n{} addon(s) total.", config.addons.len());
 
     Ok(())
 }
@@ -1,7 +1,18 @@
+pub mod adopt;
 pub mod cache;
+pub mod changelog;
+pub mod config;
+pub mod gitignore;
+pub mod import;
 pub mod init;
 pub mod install;
 pub mod list;
+pub mod outdated;
+pub mod package;
+pub mod rollback;
+pub mod search;
 pub mod uninstall;
 pub mod upgrade;
+pub mod verify;
 pub mod version;
+pub mod workspace;
@@ -0,0 +1,222 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::commands::upgrade::{GroupTarget, pinned_skips, policy_target};
+use crate::config::{Addon, Config};
+use crate::error::{Error, Result};
+use crate::git::{
+    Track, commit_timestamp, count_commits, ensure_sources, exact_tag, latest_rev, repo_cache_dir,
+    rev_parse, short_hash,
+};
+use crate::output;
+use crate::package::addon_version_at;
+use crate::project::Project;
+use crate::utils::{format_age, get_cache_dir};
+
+/// Exit code when at least one addon has updates available. Error codes stay
+/// below 20, see the Exit Codes page of the docs.
+pub const EXIT_UPDATES_AVAILABLE: i32 = 20;
+
+#[derive(Debug, Serialize)]
+struct OutdatedAddon {
+    name: String,
+    source: String,
+    current_commit: String,
+    current_version: String,
+    latest_commit: String,
+    latest_tag: Option<String>,
+    latest_version: Option<String>,
+    /// Unix timestamp of the latest commit
+    latest_commit_time: i64,
+    /// Number of commits between the locked and the latest commit
+    behind: usize,
+    /// Commit `gdam upgrade` would move the addon to, none when held back
+    target_commit: Option<String>,
+    /// Why `gdam upgrade` leaves the addon alone, e.g. `pinned` or its policy
+    held_back: Option<String>,
+    /// Whether `gdam upgrade` would move the addon
+    outdated: bool,
+}
+
+/// Report the available updates, returns whether any addon is outdated
+pub fn execute(project: &Project) -> Result<bool> {
+    let json = output::is_json();
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let config = Config::load(project)?;
+    let cache_dir = get_cache_dir()?;
+
+    // Only the cache is touched
+    if !json && !config.addons.is_empty() {
+        status!("Fetching {} source(s)...", config.addons_by_source().len());
+    }
+    ensure_sources(
+        project,
+        config.addons.iter().map(|a| a.source.as_str()),
+        &cache_dir,
+    )?;
+
+    let mut rows = Vec::new();
+
+    // Addons from the same source are upgraded together, by the same rules as `gdam upgrade`
+    for group in config.addons_by_source() {
+        let group: Vec<Addon> = group.into_iter().cloned().collect();
+        let clone_dir = repo_cache_dir(&cache_dir, &group[0].source)?;
+        let target = match pinned_skips(&group, None) {
+            Some(reasons) => GroupTarget::Skip(reasons),
+            None => policy_target(&clone_dir, &group, &group[0])?,
+        };
+
+        for addon in &group {
+            let track = addon.track.as_deref().map(Track::parse);
+            let rev = latest_rev(&clone_dir, track.as_ref())?;
+            let latest_commit = rev_parse(&clone_dir, &rev)?;
+            let behind = if latest_commit == addon.checksum {
+                0
+            } else {
+                count_commits(&clone_dir, &addon.checksum, &latest_commit).unwrap_or(0)
+            };
+            let (target_commit, held_back) = match &target {
+                GroupTarget::Commit(hash) => (Some(hash.clone()), None),
+                GroupTarget::Skip(reasons) => (
+                    None,
+                    reasons
+                        .iter()
+                        .find(|(name, _)| *name == addon.name)
+                        .map(|(_, reason)| reason.clone()),
+                ),
+            };
+
+            rows.push(OutdatedAddon {
+                name: addon.name.clone(),
+                source: addon.source.clone(),
+                current_commit: addon.checksum.clone(),
+                current_version: addon.version.clone(),
+                latest_tag: exact_tag(&clone_dir, &latest_commit),
+                latest_version: addon_version_at(&clone_dir, &latest_commit, addon.upstream_name()),
+                latest_commit_time: commit_timestamp(&clone_dir, &latest_commit)?,
+                outdated: target_commit.as_ref().is_some_and(|t| *t != addon.checksum),
+                latest_commit,
+                behind,
+                target_commit,
+                held_back,
+            });
+        }
+    }
+    rows.sort_by_key(|r| config.addons.iter().position(|a| a.name == r.name));
+
+    let outdated_count = rows.iter().filter(|r| r.outdated).count();
+
+    if json {
+        output::print_json(&rows)?;
+    } else {
+        print_table(&rows);
+
+        // Held back addons are expected to fall behind, they don't count as outdated
+        let held_back: Vec<&OutdatedAddon> = rows
+            .iter()
+            .filter(|r| r.held_back.is_some() && r.behind > 0)
+            .collect();
+        if !held_back.is_empty() {
+            status!("\nHeld back by upgrade policy:");
+            for row in held_back {
+                status!(
+                    "  - {}: {}",
+                    row.name,
+                    row.held_back.as_deref().unwrap_or_default()
+                );
+            }
+        }
+
+        if outdated_count == 0 {
+            status!("\n{}", "All addons are up to date.".green());
+        } else {
+            status!(
+                "\n{}",
+                format!(
+                    "{} addon(s) can be updated. Run 'gdam upgrade' to update.",
+                    outdated_count
+                )
+                .yellow()
+            );
+        }
+    }
+
+    Ok(outdated_count > 0)
+}
+
+fn print_table(rows: &[OutdatedAddon]) {
+    if rows.is_empty() {
+        status!("No addons installed.");
+        return;
+    }
+
+    let headers = [
+        "Addon",
+        "Current",
+        "Version",
+        "Latest",
+        "Latest Version",
+        "Age",
+    ];
+    let cells: Vec<[String; 6]> = rows
+        .iter()
+        .map(|r| {
+            let latest = match &r.latest_tag {
+                Some(tag) => tag.clone(),
+                None => short_hash(&r.latest_commit).to_string(),
+            };
+            let latest = if r.behind > 0 {
+                format!("{} (+{})", latest, r.behind)
+            } else {
+                latest
+            };
+            [
+                r.name.clone(),
+                short_hash(&r.current_commit).to_string(),
+                display_version(&r.current_version),
+                latest,
+                display_version(r.latest_version.as_deref().unwrap_or_default()),
+                format_age(r.latest_commit_time),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header: Vec<String> = headers
+        .iter()
+        .zip(widths)
+        .map(|(h, w)| format!("{:<w$}", h, w = w))
+        .collect();
+    status!("\n  {}", header.join("  ").trim_end().bold());
+
+    for (row, data) in cells.iter().zip(rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(c, w)| format!("{:<w$}", c, w = w))
+            .collect();
+        let line = line.join("  ").trim_end().to_string();
+        if data.outdated {
+            status!("  {}", line.yellow());
+        } else {
+            status!("  {}", line.dimmed());
+        }
+    }
+}
+
+fn display_version(version: &str) -> String {
+    if version.is_empty() {
+        "unknown".to_string()
+    } else {
+        version.to_string()
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::cli::PackageAction;
+use crate::error::{Error, Result};
+use crate::git::expand_source;
+use crate::output;
+use crate::package::{PACKAGE_FILE, PackageManifest, provided_addons};
+use crate::plugin::parse_plugin_cfg;
+use crate::utils::{check_autoload, is_excluded};
+use crate::version::{Version, VersionReq};
+
+#[derive(Serialize)]
+struct ValidateOutput<'a> {
+    valid: bool,
+    name: Option<&'a str>,
+    addons: Vec<String>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Run a package command, returns whether its checks passed
+pub fn execute(action: PackageAction, dir: &Path) -> Result<bool> {
+    match action {
+        PackageAction::Validate { path } => validate(&path.unwrap_or_else(|| dir.to_path_buf())),
+    }
+}
+
+/// Validate a package manifest, printing every problem found. Returns whether
+/// it has no errors.
+fn validate(repo_dir: &Path) -> Result<bool> {
+    status!("Validating {}", repo_dir.join(PACKAGE_FILE).display());
+
+    let manifest = PackageManifest::load(repo_dir)?
+        .ok_or_else(|| Error::Other(format!("No {} found", PACKAGE_FILE)))?;
+
+    let mut errors: Vec<String> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    // Metadata
+    if manifest.name.is_none() {
+        warnings.push("Missing 'name'".to_string());
+    }
+    match &manifest.version {
+        None => warnings.push("Missing 'version'".to_string()),
+        Some(v) if Version::parse(v).is_none() => {
+            warnings.push(format!("Version '{}' is not a semantic version", v))
+        }
+        Some(_) => {}
+    }
+    if manifest.license.is_none() {
+        warnings.push("Missing 'license'".to_string());
+    }
+    if let Some(godot) = &manifest.godot
+        && VersionReq::parse(godot).is_none()
+    {
+        errors.push(format!("Invalid Godot version requirement '{}'", godot));
+    }
+
+    // Addon directories
+    let addons = match provided_addons(repo_dir, Some(&manifest)) {
+        Ok(addons) => addons,
+        Err(e) => {
+            errors.push(e.to_string());
+            Vec::new()
+        }
+    };
+    if addons.is_empty() && errors.is_empty() {
+        errors.push("Package provides no addons".to_string());
+    }
+    for addon in &addons {
+        let plugin_cfg = addon.path.join("plugin.cfg");
+        if !plugin_cfg.exists() {
+            warnings.push(format!("Addon '{}' has no plugin.cfg", addon.name));
+            continue;
+        }
+        if is_excluded(&manifest.exclude, "plugin.cfg", false) {
+            errors.push(format!("plugin.cfg of '{}' is excluded", addon.name));
+        }
+        let plugin_version = parse_plugin_cfg(&plugin_cfg).version;
+        if let (Some(plugin_version), Some(version)) = (&plugin_version, &manifest.version)
+            && plugin_version != version
+        {
+            warnings.push(format!(
+                "Addon '{}' has version {} in plugin.cfg, but the package version is {}",
+                addon.name, plugin_version, version
+            ));
+        }
+    }
+
+    // Dependencies
+    for dep in &manifest.dependencies {
+        if expand_source(&dep.source, &BTreeMap::new()).is_none() {
+            errors.push(format!("Invalid dependency source '{}'", dep.source));
+        }
+    }
+
+    // Autoloads
+    for (i, autoload) in manifest.autoloads.iter().enumerate() {
+        if manifest.autoloads[..i]
+            .iter()
+            .any(|a| a.name == autoload.name)
+        {
+            errors.push(format!("Duplicate autoload '{}'", autoload.name));
+        }
+        if let Err(e) = check_autoload(&autoload.name, &autoload.path) {
+            errors.push(e.to_string());
+            continue;
+        }
+
+        let target = autoload
+            .path
+            .strip_prefix("res://addons/")
+            .and_then(|rest| rest.split_once('/'))
+            .and_then(|(name, rest)| {
+                addons
+                    .iter()
+                    .find(|a| a.name == name)
+                    .map(|a| a.path.join(rest))
+            });
+        match target {
+            Some(path) if path.exists() => {}
+            Some(_) => errors.push(format!(
+                "Autoload '{}' points to missing file {}",
+                autoload.name, autoload.path
+            )),
+            None => errors.push(format!(
+                "Autoload '{}' must point into one of the package's addons (res://addons/<addon>/...)",
+                autoload.name
+            )),
+        }
+    }
+
+    if output::is_json() {
+        let valid = errors.is_empty();
+        output::print_json(&ValidateOutput {
+            valid,
+            name: manifest.name.as_deref(),
+            addons: addons.into_iter().map(|a| a.name).collect(),
+            errors,
+            warnings,
+        })?;
+        return Ok(valid);
+    }
+
+    for warning in &warnings {
+        status!("{}", format!("  warning: {}", warning).yellow());
+    }
+    for error in &errors {
+        status!("{}", format!("  error: {}", error).red());
+    }
+
+    if !errors.is_empty() {
+        eprintln!(
+            "{}",
+            format!("Package validation failed with {} error(s)", errors.len()).red()
+        );
+        return Ok(false);
+    }
+
+    let name = manifest.name.as_deref().unwrap_or("package");
+    status!(
+        "{}",
+        format!(
+            "{} is valid ({} addon(s), {} warning(s))",
+            name,
+            addons.len(),
+            warnings.len()
+        )
+        .green()
+    );
+    Ok(true)
+}
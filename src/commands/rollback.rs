@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::commands::uninstall::remove_addon_dir;
+use crate::config::{Addon, Config};
+use crate::error::{Error, Result};
+use crate::git::{checkout, ensure_repo, repo_cache_dir, same_source, short_hash};
+use crate::gitignore;
+use crate::history::History;
+use crate::package::{PackageManifest, copy_addon, find_provided_addon};
+use crate::project::Project;
+use crate::utils::{format_age, get_cache_dir};
+
+pub fn execute(project: &Project, addon_name: Option<String>) -> Result<()> {
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let mut config = Config::load(project)?;
+    let mut history = History::load(project)?;
+
+    // Revert the whole last operation, or the last change of one source
+    let operation = match &addon_name {
+        Some(name) => {
+            // Addons from the same source always move together
+            let source = config
+                .addons
+                .iter()
+                .find(|a| a.name == *name)
+                .map(|a| a.source.clone());
+            history
+                .take(|change| {
+                    change.name == *name
+                        || config.addons.iter().any(|a| {
+                            a.name == change.name
+                                && source.as_ref().is_some_and(|s| same_source(s, &a.source))
+                        })
+                })
+                .ok_or_else(|| {
+                    Error::Other(format!("No previous version of '{}' recorded", name))
+                })?
+        }
+        None => history
+            .take(|_| true)
+            .ok_or_else(|| Error::Other("Nothing to roll back".to_string()))?,
+    };
+    status!(
+        "Rolling back {} from {}...",
+        operation.command,
+        format_age(operation.time)
+    );
+    let changes = operation.changes;
+
+    let cache_dir = get_cache_dir()?;
+    let mut fetched: Vec<String> = Vec::new();
+
+    for change in &changes {
+        match &change.previous {
+            Some(previous) => {
+                restore_addon(project, previous, &cache_dir, &mut fetched)?;
+                let version = if previous.version.is_empty() {
+                    "unknown"
+                } else {
+                    &previous.version
+                };
+                status!(
+                    "  Restored: {} ({}, {})",
+                    previous.name.green(),
+                    version,
+                    short_hash(&previous.checksum)
+                );
+
+                match config.addons.iter_mut().find(|a| a.name == previous.name) {
+                    Some(addon) => *addon = previous.clone(),
+                    None => config.addons.push(previous.clone()),
+                }
+            }
+            None => {
+                // Newly installed by the operation
+                remove_addon_dir(project, &change.name)?;
+                config.addons.retain(|a| a.name != change.name);
+                status!("  Removed: {}", change.name.green());
+            }
+        }
+    }
+
+    config.save(project)?;
+    gitignore::sync(project, &config)?;
+    history.save(project)?;
+
+    status!(
+        "{}",
+        format!("Rolled back {} addon(s) successfully!", changes.len()).green()
+    );
+
+    Ok(())
+}
+
+/// Copy an addon into the project at a previously installed commit
+fn restore_addon(
+    project: &Project,
+    addon: &Addon,
+    cache_dir: &Path,
+    fetched: &mut Vec<String>,
+) -> Result<()> {
+    let clone_dir = repo_cache_dir(cache_dir, &addon.source)?;
+
+    if !fetched.iter().any(|s| same_source(s, &addon.source)) {
+        ensure_repo(project, &addon.source, &clone_dir)?;
+        fetched.push(addon.source.clone());
+    }
+    checkout(&clone_dir, &addon.checksum)?;
+
+    let provided = find_provided_addon(&clone_dir, addon.upstream_name())?.ok_or_else(|| {
+        Error::AddonMissingInRepo(format!(
+            "'{}' at commit {}",
+            addon.upstream_name(),
+            addon.checksum
+        ))
+    })?;
+    let manifest = PackageManifest::load(&clone_dir)?;
+    copy_addon(
+        &provided.path,
+        &project.addons_dir().join(&addon.name),
+        manifest.as_ref(),
+    )
+}
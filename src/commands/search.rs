@@ -0,0 +1,112 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::git::{expand_source, same_source};
+use crate::output;
+use crate::project::Project;
+use crate::registry::load_entries;
+use crate::utils::get_godot_version;
+use crate::version::VersionReq;
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    name: &'a str,
+    source: &'a str,
+    description: Option<&'a str>,
+    tags: &'a [String],
+    godot: Option<&'a str>,
+    installed: bool,
+    compatible: bool,
+}
+
+pub fn execute(project: &Project, term: String) -> Result<()> {
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let config = Config::load(project)?;
+    let registries = config.effective_registries();
+    if registries.is_empty() {
+        return Err(Error::Other(
+            "No registries configured. Add index files or git URLs to 'registries' in gdam.yaml, or set a default with 'gdam config set registry <location>'"
+                .to_string(),
+        ));
+    }
+
+    let results: Vec<_> = load_entries(project, &registries)?
+        .into_iter()
+        .filter(|e| e.matches(&term))
+        .collect();
+
+    let project_version = get_godot_version(project);
+    let is_installed = |source: &str| {
+        let source = expand_source(source, &config.source_schemes);
+        config
+            .addons
+            .iter()
+            .any(|a| source.as_ref().is_some_and(|s| same_source(s, &a.source)))
+    };
+    let is_compatible = |godot: Option<&str>| {
+        godot
+            .and_then(VersionReq::parse)
+            .zip(project_version.as_ref())
+            .is_none_or(|(req, version)| req.matches(version))
+    };
+
+    if output::is_json() {
+        let results: Vec<SearchResult> = results
+            .iter()
+            .map(|entry| SearchResult {
+                name: &entry.name,
+                source: &entry.source,
+                description: entry.description.as_deref(),
+                tags: &entry.tags,
+                godot: entry.godot.as_deref(),
+                installed: is_installed(&entry.source),
+                compatible: is_compatible(entry.godot.as_deref()),
+            })
+            .collect();
+        return output::print_json(&results);
+    }
+
+    if results.is_empty() {
+        status!("No addons matching '{}' found.", term);
+        return Ok(());
+    }
+
+    status!("Found {} addon(s):\n", results.len());
+    for entry in &results {
+        let installed = is_installed(&entry.source);
+        if installed {
+            status!("  {} {}", entry.name.green().bold(), "(installed)".dimmed());
+        } else {
+            status!("  {}", entry.name.green().bold());
+        }
+        if let Some(description) = &entry.description {
+            status!("    {}", description);
+        }
+        status!("    {}", entry.source.dimmed());
+
+        let mut details = Vec::new();
+        if !entry.tags.is_empty() {
+            details.push(format!("tags: {}", entry.tags.join(", ")));
+        }
+        if let Some(godot) = &entry.godot {
+            details.push(format!("Godot {}", godot));
+        }
+        if !details.is_empty() {
+            status!("    {}", details.join(" | ").dimmed());
+        }
+
+        if !is_compatible(entry.godot.as_deref()) {
+            status!(
+                "    {}",
+                "Not compatible with this project's Godot version".yellow()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,52 +1,100 @@
 use std::fs;
-use std::path::Path;
 
 use colored::Colorize;
 
 use crate::config::{ADDONS_DIR, Config};
 use crate::error::{Error, Result};
-use crate::utils::ensure_in_godot_project;
+use crate::git::{expand_source, same_source};
+use crate::gitignore;
+use crate::history::{Change, History};
+use crate::project::Project;
+use crate::utils::remove_addon_autoloads;
 
-pub fn execute(query: String) -> Result<()> {
-    ensure_in_godot_project()?;
-
-    if !Config::exists() {
+pub fn execute(project: &Project, query: String) -> Result<()> {
+    if !Config::exists(project) {
         return Err(Error::ConfigNotFound);
     }
 
-    let mut config = Config::load()?;
+    let mut config = Config::load(project)?;
 
-    // Find addon by name or source (exact match)
-    let addon = config
-        .addons
-        .iter()
-        .find(|a| a.name == query || a.source == query);
+    // Find addon by name or source (shorthand and equivalent URLs match)
+    let source_query = expand_source(&query, &config.source_schemes);
+    let addon = config.addons.iter().find(|a| {
+        a.name == query
+            || source_query
+                .as_ref()
+                .is_some_and(|s| same_source(s, &a.source))
+    });
 
-    let addon_name = match addon {
-        Some(a) => a.name.clone(),
+    let (addon_name, source) = match addon {
+        Some(a) => (a.name.clone(), a.source.clone()),
         None => {
-            return Err(Error::Other(format!(
-                "Addon '{}' not found in gdam.yaml",
-                query
-            )));
+            return Err(Error::AddonNotFound(format!("'{}' in gdam.yaml", query)));
         }
     };
 
-    // Remove addon directory
-    let addon_path = Path::new(ADDONS_DIR).join(&addon_name);
-    if addon_path.exists() {
-        fs::remove_dir_all(&addon_path)?;
-        println!("Removed directory: {}", addon_path.display());
+    // Refuse to remove a dependency other addons still need
+    let dependents: Vec<String> = config
+        .dependents_of(&source)
+        .iter()
+        .map(|a| a.name.clone())
+        .collect();
+    if !dependents.is_empty() {
+        return Err(Error::Other(format!(
+            "Addon '{}' is required by: {}",
+            addon_name,
+            dependents.join(", ")
+        )));
     }
 
-    // Remove from config
+    remove_addon_dir(project, &addon_name)?;
+
+    // Remove from config, along with dependencies nothing else needs
+    let mut changes: Vec<Change> = config
+        .addons
+        .iter()
+        .filter(|a| a.name == addon_name)
+        .map(|a| Change {
+            name: a.name.clone(),
+            previous: Some(a.clone()),
+        })
+        .collect();
     config.addons.retain(|a| a.name != addon_name);
-    config.save()?;
+    let pruned = config.prune_dependencies();
+    for dep in &pruned {
+        remove_addon_dir(project, &dep.name)?;
+        changes.push(Change {
+            name: dep.name.clone(),
+            previous: Some(dep.clone()),
+        });
+    }
+    config.save(project)?;
+    gitignore::sync(project, &config)?;
+    History::record(project, "uninstall", changes)?;
 
-    println!(
+    status!(
         "{}",
         format!("Uninstalled '{}' successfully!", addon_name).green()
     );
+    if !pruned.is_empty() {
+        status!("Removed {} unused dependency addon(s):", pruned.len());
+        for dep in &pruned {
+            status!("  - {}", dep.name);
+        }
+    }
+
+    Ok(())
+}
 
+/// Remove an addon directory and its autoloads from the project
+pub fn remove_addon_dir(project: &Project, addon_name: &str) -> Result<()> {
+    let addon_path = project.addons_dir().join(addon_name);
+    if addon_path.exists() {
+        fs::remove_dir_all(&addon_path)?;
+        status!("Removed directory: {}/{}", ADDONS_DIR, addon_name);
+    }
+    for autoload in remove_addon_autoloads(project, addon_name)? {
+        status!("Removed autoload: {}", autoload);
+    }
     Ok(())
 }
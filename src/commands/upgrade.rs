@@ -1,122 +1,848 @@
-use std::fs;
+use std::io::{self, Write};
 use std::path::Path;
 
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::config::{ADDONS_DIR, Addon, Config};
+use crate::changelog;
+use crate::config::{Addon, Config, UpgradePolicy};
 use crate::error::{Error, Result};
-use crate::git::{checkout_latest, ensure_repo, extract_repo_name, get_commit_hash};
+use crate::git::{
+    Track, checkout, diff_name_status, ensure_repo, exact_tag, is_ancestor, latest_rev, list_tags,
+    list_tags_merged, log_oneline, repo_cache_dir, rev_parse, short_hash,
+};
+use crate::gitignore;
+use crate::history::{Change, History};
+use crate::output::{self, Upgraded};
+use crate::package::{
+    PackageManifest, addon_version_at, copy_addon, provided_addon_names_at, provided_addon_path_at,
+    provided_addons,
+};
 use crate::plugin::parse_plugin_cfg;
-use crate::utils::{copy_dir_recursive, ensure_in_godot_project, get_cache_dir};
+use crate::project::Project;
+use crate::utils::{confirm, get_cache_dir, is_interactive};
+use crate::version::{Version, is_prerelease};
 
-pub fn execute(addon_name: Option<String>) -> Result<()> {
-    ensure_in_godot_project()?;
+/// Options for upgrading addons
+pub struct UpgradeOptions {
+    /// Only show what would change
+    pub dry_run: bool,
+    /// Show the changes of each addon and ask before applying them
+    pub confirm: bool,
+    /// Branch, tag or commit to move the addon to instead of its latest version
+    pub to: Option<String>,
+}
+
+/// What `upgrade --dry-run` would change, the JSON result of a dry run
+#[derive(Debug, Default, Serialize)]
+pub struct UpgradePreview {
+    upgraded: Vec<Upgraded>,
+    /// Addons that appeared upstream, offered for install on upgrade
+    new_upstream: Vec<String>,
+    /// Installed addons no longer provided upstream
+    removed_upstream: Vec<String>,
+    skipped: Vec<SkippedAddon>,
+}
 
-    if !Config::exists() {
+#[derive(Debug, Serialize)]
+struct SkippedAddon {
+    name: String,
+    reason: String,
+}
+
+/// Upgrade the addons of a project, returns what a dry run would change
+pub fn execute(
+    project: &Project,
+    addon_name: Option<String>,
+    options: UpgradeOptions,
+) -> Result<UpgradePreview> {
+    if !Config::exists(project) {
         return Err(Error::ConfigNotFound);
     }
 
-    let mut config = Config::load()?;
+    let mut config = Config::load(project)?;
+    let mut preview = UpgradePreview::default();
 
     if config.addons.is_empty() {
-        println!("No addons to upgrade.");
-        return Ok(());
-    }
-
-    // Filter addons to upgrade
-    let addons_to_upgrade: Vec<&Addon> = match &addon_name {
-        Some(name) => {
-            let addon = config.addons.iter().find(|a| a.name == *name);
-            match addon {
-                Some(a) => vec![a],
-                None => {
-                    return Err(Error::Other(format!("Addon '{}' not found", name)));
-                }
-            }
-        }
-        None => config.addons.iter().collect(),
-    };
+        status!("No addons to upgrade.");
+        return Ok(preview);
+    }
+
+    // Addons from the same source are always upgraded together
+    if let Some(name) = &addon_name
+        && !config.addons.iter().any(|a| a.name == *name)
+    {
+        return Err(Error::AddonNotFound(format!("'{}' in gdam.yaml", name)));
+    }
+    let groups: Vec<Vec<Addon>> = config
+        .addons_by_source()
+        .into_iter()
+        .filter(|g| {
+            addon_name
+                .as_ref()
+                .is_none_or(|n| g.iter().any(|a| a.name == *n))
+        })
+        .map(|g| g.into_iter().cloned().collect())
+        .collect();
 
-    println!(
+    status!(
         "Checking {} addon(s) for updates...",
-        addons_to_upgrade.len()
+        groups.iter().map(Vec::len).sum::<usize>()
     );
 
     let cache_dir = get_cache_dir()?;
-    let local_addons_dir = Path::new(ADDONS_DIR);
-    let mut updated_addons: Vec<(String, String, String)> = Vec::new(); // (name, old_hash, new_hash)
+    let local_addons_dir = project.addons_dir();
+    let mut updated_addons: Vec<(String, String, String)> = Vec::new(); // (name, new_hash, new_version)
+    let mut new_addons: Vec<Addon> = Vec::new();
+    let mut removed_upstream: Vec<String> = Vec::new();
+    let mut available_count = 0;
+    let mut skipped: Vec<(String, String)> = Vec::new(); // (name, reason)
 
-    for addon in &addons_to_upgrade {
-        print!("  Checking: {}... ", addon.name);
+    for group in &groups {
+        // The addon named on the command line decides the target, or the first one of the source
+        let lead = group
+            .iter()
+            .find(|a| Some(&a.name) == addon_name.as_ref())
+            .unwrap_or(&group[0]);
+        let names: Vec<&str> = group.iter().map(|a| a.name.as_str()).collect();
+        if output::is_json() {
+            eprint!("  Checking: {}... ", names.join(", "));
+        } else {
+            print!("  Checking: {}... ", names.join(", "));
+            io::stdout().flush()?;
+        }
 
-        let repo_name = extract_repo_name(&addon.source)
-            .ok_or_else(|| Error::InvalidUrl(addon.source.clone()))?;
+        let skip = |reasons: Vec<(String, String)>, skipped: &mut Vec<(String, String)>| {
+            let reason = reasons.iter().find(|(name, _)| *name == lead.name);
+            if let Some((_, reason)) = reason {
+                status!("{}", format!("skipped ({})", reason).dimmed());
+            }
+            skipped.extend(reasons);
+        };
+        if let Some(reasons) = pinned_skips(group, addon_name.as_deref()) {
+            skip(reasons, &mut skipped);
+            continue;
+        }
 
-        // Ensure repo exists and checkout latest
-        let clone_dir = cache_dir.join(&repo_name);
-        ensure_repo(&addon.source, &clone_dir)?;
-        checkout_latest(&clone_dir)?;
-        let new_hash = get_commit_hash(&clone_dir)?;
+        // Ensure repo exists and find the target commit without touching the working tree
+        let clone_dir = repo_cache_dir(&cache_dir, &lead.source)?;
+        ensure_repo(project, &lead.source, &clone_dir)?;
+        let target = match &options.to {
+            Some(to) => GroupTarget::Commit(resolve_ref(&clone_dir, to)?),
+            None => policy_target(&clone_dir, group, lead)?,
+        };
+        let latest_hash = match target {
+            GroupTarget::Commit(hash) => hash,
+            GroupTarget::Skip(reasons) => {
+                skip(reasons, &mut skipped);
+                continue;
+            }
+        };
 
-        if new_hash == addon.checksum {
-            println!("{}", "already up to date".dimmed());
+        // Addons added and removed upstream since the installed commit
+        let upstream_names = provided_addon_names_at(&clone_dir, &latest_hash)?;
+        let added: Vec<&String> = upstream_names
+            .iter()
+            .filter(|n| !group.iter().any(|a| a.upstream_name() == n.as_str()))
+            .collect();
+        let (current, removed): (Vec<&Addon>, Vec<&Addon>) = group
+            .iter()
+            .partition(|a| upstream_names.iter().any(|n| n == a.upstream_name()));
+
+        let outdated: Vec<&Addon> = current
+            .into_iter()
+            .filter(|a| a.checksum != latest_hash)
+            .collect();
+        if outdated.is_empty() && added.is_empty() {
+            status!("{}", "already up to date".dimmed());
+            for addon in &removed {
+                warn_removed_upstream(addon);
+                removed_upstream.push(addon.name.clone());
+            }
             continue;
         }
 
-        println!("{}", "updating".yellow());
+        available_count += outdated.len();
 
-        // Copy new version
-        let src_addon_path = clone_dir.join("addons").join(&addon.name);
-        if !src_addon_path.exists() {
-            println!(
-                "{}",
-                format!(
-                    "    Warning: addon '{}' not found in latest version",
-                    addon.name
-                )
-                .yellow()
-            );
+        if options.dry_run || options.confirm {
+            status!("{}", "update available".yellow());
+            let from = outdated.first().map_or(&lead.checksum, |a| &a.checksum);
+            print_commits(&clone_dir, from, &latest_hash);
+            for addon in &outdated {
+                if group.len() > 1 {
+                    status!("    {}", addon.name.bold());
+                }
+                print_preview(&clone_dir, addon, &latest_hash);
+                preview.upgraded.push(Upgraded {
+                    name: addon.name.clone(),
+                    source: addon.source.clone(),
+                    from_version: addon.version.clone(),
+                    to_version: addon_version_at(&clone_dir, &latest_hash, addon.upstream_name())
+                        .unwrap_or_default(),
+                    from_commit: addon.checksum.clone(),
+                    to_commit: latest_hash.clone(),
+                });
+            }
+        } else {
+            status!("{}", "updating".yellow());
+        }
+        for name in &added {
+            status!("{}", format!("    New addon upstream: {}", name).green());
+        }
+        for addon in &removed {
+            warn_removed_upstream(addon);
+            removed_upstream.push(addon.name.clone());
+        }
+
+        if options.dry_run {
+            preview.new_upstream.extend(added.into_iter().cloned());
+            continue;
+        }
+        if options.confirm
+            && !outdated.is_empty()
+            && !confirm(&format!("  Upgrade {}?", names.join(", ")))?
+        {
+            status!("  Skipped {}", names.join(", "));
             continue;
         }
 
-        let dst_addon_path = local_addons_dir.join(&addon.name);
-        if dst_addon_path.exists() {
-            fs::remove_dir_all(&dst_addon_path)?;
+        checkout(&clone_dir, &latest_hash)?;
+        let manifest = PackageManifest::load(&clone_dir)?;
+        let provided = provided_addons(&clone_dir, manifest.as_ref())?;
+
+        for addon in &outdated {
+            let Some(src) = provided.iter().find(|p| p.name == addon.upstream_name()) else {
+                continue;
+            };
+
+            let dst_addon_path = local_addons_dir.join(&addon.name);
+            copy_addon(&src.path, &dst_addon_path, manifest.as_ref())?;
+            let new_version = installed_version(&dst_addon_path, manifest.as_ref());
+
+            // Already shown in the preview when confirming
+            if !options.confirm {
+                print_changelog(&clone_dir, &latest_hash, addon, &new_version);
+            }
+
+            updated_addons.push((addon.name.clone(), latest_hash.clone(), new_version));
+        }
+
+        // Offer to install addons that appeared upstream
+        for name in added {
+            if config.addons.iter().any(|a| a.name == *name) || local_addons_dir.join(name).exists()
+            {
+                status!(
+                    "{}",
+                    format!("    addons/{} already exists, not installing it", name).yellow()
+                );
+                continue;
+            }
+            // Never wait for an answer nobody can give, e.g. in CI
+            if !options.confirm && !is_interactive() {
+                status!(
+                    "    Not installing new addon '{}', run 'gdam upgrade --confirm' to be asked",
+                    name
+                );
+                continue;
+            }
+            if !confirm(&format!(
+                "  Install new addon '{}' from {}?",
+                name, lead.source
+            ))? {
+                continue;
+            }
+            let Some(src) = provided.iter().find(|p| p.name == *name) else {
+                continue;
+            };
+
+            let dst_addon_path = local_addons_dir.join(name);
+            copy_addon(&src.path, &dst_addon_path, manifest.as_ref())?;
+            new_addons.push(Addon {
+                name: name.clone(),
+                version: installed_version(&dst_addon_path, manifest.as_ref()),
+                source: lead.source.clone(),
+                checksum: latest_hash.clone(),
+                track: lead.track.clone(),
+                transitive: lead.transitive,
+                dependencies: lead.dependencies.clone(),
+                upstream_name: None,
+                policy: None,
+            });
+        }
+    }
+
+    if !skipped.is_empty() {
+        status!("\nSkipped {} addon(s) by upgrade policy:", skipped.len());
+        for (name, reason) in &skipped {
+            status!("  - {}: {}", name, reason.dimmed());
         }
-        copy_dir_recursive(&src_addon_path, &dst_addon_path)?;
+    }
 
-        // Get new version from plugin.cfg
-        let plugin_cfg_path = dst_addon_path.join("plugin.cfg");
-        let plugin_info = parse_plugin_cfg(&plugin_cfg_path);
-        let new_version = plugin_info.version.unwrap_or_default();
+    if !removed_upstream.is_empty() {
+        status!(
+            "\n{}",
+            format!(
+                "{} addon(s) no longer exist upstream and were left untouched:",
+                removed_upstream.len()
+            )
+            .yellow()
+        );
+        for name in &removed_upstream {
+            status!("  - {} (run 'gdam uninstall {}' to remove it)", name, name);
+        }
+    }
 
-        updated_addons.push((addon.name.clone(), new_hash.clone(), new_version));
+    if options.dry_run {
+        preview.removed_upstream = removed_upstream;
+        preview.skipped = skipped
+            .into_iter()
+            .map(|(name, reason)| SkippedAddon { name, reason })
+            .collect();
+        if available_count == 0 {
+            status!("\n{}", "All addons are up to date.".green());
+        } else {
+            status!(
+                "\n{}",
+                format!(
+                    "{} addon(s) would be updated. Nothing was changed (dry run).",
+                    available_count
+                )
+                .yellow()
+            );
+        }
+        return Ok(preview);
     }
 
-    // Update config with new versions
+    // Update config with new versions, remembering the previous ones for rollback
+    let mut changes = Vec::new();
     for (name, new_hash, new_version) in &updated_addons {
         if let Some(addon) = config.addons.iter_mut().find(|a| a.name == *name) {
+            changes.push(Change {
+                name: name.clone(),
+                previous: Some(addon.clone()),
+            });
             addon.checksum = new_hash.clone();
             addon.version = new_version.clone();
+            // Keep plain upgrades from moving the addon off the requested ref
+            if options.to.is_some() {
+                addon.policy = Some(UpgradePolicy::Pinned);
+            }
         }
     }
 
+    if !new_addons.is_empty() {
+        status!(
+            "\n{}",
+            format!("Installed {} new addon(s):", new_addons.len()).green()
+        );
+        for addon in &new_addons {
+            status!("  - {}", addon.name.green());
+            changes.push(Change {
+                name: addon.name.clone(),
+                previous: None,
+            });
+        }
+        config.addons.extend(new_addons);
+        config.save(project)?;
+        gitignore::sync(project, &config)?;
+    }
+
+    History::record(project, "upgrade", changes)?;
+
     if !updated_addons.is_empty() {
-        config.save()?;
-        println!(
+        config.save(project)?;
+        status!(
             "\n{}",
             format!("Updated {} addon(s):", updated_addons.len()).green()
         );
         for (name, _, version) in &updated_addons {
             if version.is_empty() {
-                println!("  - {}", name.green());
+                status!("  - {}", name.green());
             } else {
-                println!("  - {} ({})", name.green(), version);
+                status!("  - {} ({})", name.green(), version);
             }
         }
+        if let Some(to) = &options.to {
+            status!(
+                "Pinned to {}: 'gdam upgrade' skips these addons until 'policy: pinned' is removed from gdam.yaml, 'gdam upgrade <addon-name>' still moves them",
+                to
+            );
+        }
+    } else if available_count > 0 {
+        status!("\n{}", "No addons were updated.".yellow());
     } else {
-        println!("\n{}", "All addons are up to date.".green());
+        status!("\n{}", "All addons are up to date.".green());
     }
 
-    Ok(())
+    Ok(preview)
+}
+
+/// Get the version of an installed addon from its plugin.cfg, falling back to the package version
+fn installed_version(addon_dir: &Path, manifest: Option<&PackageManifest>) -> String {
+    parse_plugin_cfg(&addon_dir.join("plugin.cfg"))
+        .version
+        .or_else(|| manifest.and_then(|m| m.version.clone()))
+        .unwrap_or_default()
+}
+
+fn warn_removed_upstream(addon: &Addon) {
+    status!(
+        "{}",
+        format!(
+            "    Warning: addon '{}' no longer exists upstream",
+            addon.upstream_name()
+        )
+        .yellow()
+    );
+}
+
+/// Resolve a ref given with `--to`, preferring remote branches over stale local ones
+fn resolve_ref(clone_dir: &Path, git_ref: &str) -> Result<String> {
+    rev_parse(clone_dir, &format!("origin/{}", git_ref))
+        .or_else(|_| rev_parse(clone_dir, git_ref))
+        .map_err(|_| Error::RefNotFound(git_ref.to_string()))
+}
+
+/// The addon of a group with the strictest upgrade policy, the lead addon on ties
+fn strictest_policy<'a>(group: &'a [Addon], lead: &'a Addon) -> &'a Addon {
+    let rank = |addon: &Addon| match addon.policy {
+        Some(UpgradePolicy::Patch) => 0,
+        Some(UpgradePolicy::Minor) => 1,
+        Some(UpgradePolicy::Major) => 2,
+        _ => 3,
+    };
+    std::iter::once(lead)
+        .chain(group)
+        .min_by_key(|a| rank(a))
+        .unwrap_or(lead)
+}
+
+/// Where a group of addons from the same source would be upgraded to
+pub enum GroupTarget {
+    Commit(String),
+    /// Held back, with the reason for each addon of the group
+    Skip(Vec<(String, String)>),
+}
+
+/// Pinned addons are only upgraded when named explicitly, naming a sibling
+/// from the same source doesn't move them. Returns the reason for each addon
+/// of the group if a pinned one holds it back.
+pub fn pinned_skips(group: &[Addon], named: Option<&str>) -> Option<Vec<(String, String)>> {
+    let pinned = group
+        .iter()
+        .find(|a| a.policy == Some(UpgradePolicy::Pinned) && Some(a.name.as_str()) != named)?;
+    Some(
+        group
+            .iter()
+            .map(|a| {
+                let reason = if a.name == pinned.name {
+                    "pinned".to_string()
+                } else {
+                    format!("pinned by {}", pinned.name)
+                };
+                (a.name.clone(), reason)
+            })
+            .collect(),
+    )
+}
+
+/// Find the commit a group of addons from the same source should be upgraded
+/// to, the strictest policy of the group limits all its addons
+pub fn policy_target(clone_dir: &Path, group: &[Addon], lead: &Addon) -> Result<GroupTarget> {
+    let limiting = strictest_policy(group, lead);
+    Ok(match upgrade_target(clone_dir, limiting)? {
+        Target::Commit(hash) => GroupTarget::Commit(hash),
+        Target::Skip(reason) => GroupTarget::Skip(
+            group
+                .iter()
+                .map(|a| {
+                    let reason = if a.name == limiting.name {
+                        reason.clone()
+                    } else {
+                        format!("{}, policy of {}", reason, limiting.name)
+                    };
+                    (a.name.clone(), reason)
+                })
+                .collect(),
+        ),
+    })
+}
+
+/// Where an addon would be upgraded to
+enum Target {
+    Commit(String),
+    /// Not upgraded because of the addon's policy
+    Skip(String),
+}
+
+/// The branch or tag series an addon follows on upgrade, None for the default branch
+fn followed_track(addon: &Addon) -> Option<Track> {
+    match (addon.policy, addon.track.as_deref().map(Track::parse)) {
+        // Follow the branch head instead of a tag series
+        (Some(UpgradePolicy::TrackBranch), Some(Track::Tags(_))) => None,
+        (_, track) => track,
+    }
+}
+
+/// Get the installed version of an addon, from gdam.yaml or the tag of its commit
+fn locked_version(clone_dir: &Path, addon: &Addon) -> Option<Version> {
+    Version::parse(&addon.version)
+        .or_else(|| exact_tag(clone_dir, &addon.checksum).and_then(|tag| Version::parse(&tag)))
+}
+
+/// Check if the installed version of an addon is a pre-release, going by
+/// gdam.yaml or the tag of its commit
+fn locked_prerelease(clone_dir: &Path, addon: &Addon) -> bool {
+    is_prerelease(&addon.version)
+        || exact_tag(clone_dir, &addon.checksum).is_some_and(|tag| is_prerelease(&tag))
+}
+
+/// Check if a version policy allows moving from the current to another version
+fn policy_allows(policy: UpgradePolicy, current: &Version, version: &Version) -> bool {
+    match policy {
+        UpgradePolicy::Patch => version.major == current.major && version.minor == current.minor,
+        UpgradePolicy::Minor => version.major == current.major,
+        _ => true,
+    }
+}
+
+/// Check if an addon may be moved to a commit without breaking its upgrade
+/// policy or leaving its track, returns why not otherwise. Pinned addons only
+/// move when named explicitly.
+pub fn policy_blocks(
+    clone_dir: &Path,
+    addon: &Addon,
+    commit: &str,
+    named: bool,
+) -> Result<Option<String>> {
+    if addon.policy == Some(UpgradePolicy::Pinned) && !named {
+        return Ok(Some("pinned".to_string()));
+    }
+    // Branches are followed up to their head, tag series only land on their tags
+    let on_track = match followed_track(addon) {
+        Some(track @ Track::Branch(_)) => {
+            is_ancestor(clone_dir, commit, &latest_rev(clone_dir, Some(&track))?)
+        }
+        Some(Track::Tags(pattern)) => list_tags(clone_dir, Some(&pattern))?
+            .iter()
+            .any(|tag| rev_parse(clone_dir, tag).is_ok_and(|hash| hash == commit)),
+        None => true,
+    };
+    if !on_track {
+        return Ok(Some(format!(
+            "not on its track '{}'",
+            addon.track.as_deref().unwrap_or_default()
+        )));
+    }
+
+    let policy = match addon.policy {
+        Some(p @ (UpgradePolicy::Patch | UpgradePolicy::Minor)) => p,
+        _ => return Ok(None),
+    };
+    let Some(current) = locked_version(clone_dir, addon) else {
+        return Ok(Some(format!(
+            "installed version unknown, required by {} policy",
+            policy
+        )));
+    };
+    let version = exact_tag(clone_dir, commit)
+        .and_then(|tag| Version::parse(&tag))
+        .or_else(|| {
+            addon_version_at(clone_dir, commit, addon.upstream_name())
+                .and_then(|v| Version::parse(&v))
+        });
+    Ok(match version {
+        Some(v) if policy_allows(policy, &current, &v) => None,
+        Some(v) => Some(format!("{} exceeds {} policy", v, policy)),
+        None => Some(format!("version unknown, required by {} policy", policy)),
+    })
+}
+
+/// Find the commit an addon should be upgraded to, obeying its upgrade policy
+fn upgrade_target(clone_dir: &Path, addon: &Addon) -> Result<Target> {
+    let track = followed_track(addon);
+    let latest = latest_rev(clone_dir, track.as_ref())?;
+    let latest_hash = rev_parse(clone_dir, &latest)?;
+
+    let policy = match addon.policy {
+        Some(p @ (UpgradePolicy::Patch | UpgradePolicy::Minor | UpgradePolicy::Major)) => p,
+        _ => return Ok(Target::Commit(latest_hash)),
+    };
+
+    let Some(current) = locked_version(clone_dir, addon) else {
+        return Ok(Target::Skip(format!(
+            "installed version unknown, required by {} policy",
+            policy
+        )));
+    };
+
+    let allows = |v: &Version| policy_allows(policy, &current, v);
+    // Pre-releases are only candidates when one is installed already, its
+    // final release then counts as newer
+    let prerelease = locked_prerelease(clone_dir, addon);
+    let newer = |name: &str, v: &Version| {
+        *v > current || (prerelease && *v == current && !is_prerelease(name))
+    };
+
+    // Prefer release tags reachable from the latest commit
+    let tags: Vec<(String, Version)> = list_tags_merged(clone_dir, &latest)?
+        .into_iter()
+        .filter(|tag| prerelease || !is_prerelease(tag))
+        .filter_map(|tag| Version::parse(&tag).map(|v| (tag, v)))
+        .collect();
+    if !tags.is_empty() {
+        if let Some((tag, _)) = tags.iter().find(|(t, v)| newer(t, v) && allows(v)) {
+            return Ok(Target::Commit(rev_parse(clone_dir, tag)?));
+        }
+        return Ok(match tags.iter().find(|(t, v)| newer(t, v)) {
+            Some((tag, _)) => Target::Skip(format!("{} exceeds {} policy", tag, policy)),
+            None => Target::Commit(addon.checksum.clone()),
+        });
+    }
+
+    // Without tags, compare the plugin.cfg version of the latest commit
+    let latest_version = addon_version_at(clone_dir, &latest_hash, addon.upstream_name())
+        .filter(|v| prerelease || !is_prerelease(v))
+        .and_then(|v| Version::parse(&v));
+    Ok(match latest_version {
+        Some(v) if v >= current && allows(&v) => Target::Commit(latest_hash),
+        Some(v) if v > current => Target::Skip(format!("{} exceeds {} policy", v, policy)),
+        _ => Target::Commit(addon.checksum.clone()),
+    })
+}
+
+/// Print the commits between the locked and the latest commit
+fn print_commits(clone_dir: &Path, from: &str, latest_hash: &str) {
+    status!(
+        "    Commit:  {} -> {}",
+        short_hash(from),
+        short_hash(latest_hash)
+    );
+    match log_oneline(clone_dir, from, latest_hash) {
+        Ok(commits) => {
+            status!("    Commits ({}):", commits.len());
+            for commit in &commits {
+                status!("      {}", commit.dimmed());
+            }
+        }
+        Err(_) => status!(
+            "{}",
+            "    Warning: locked commit not found in repository, cannot show log".yellow()
+        ),
+    }
+}
+
+/// Print the version change, file changes and changelog of an addon between the locked and the latest commit
+fn print_preview(clone_dir: &Path, addon: &Addon, latest_hash: &str) {
+    let name = addon.upstream_name();
+    let old_path = provided_addon_path_at(clone_dir, &addon.checksum, name);
+    let new_path = provided_addon_path_at(clone_dir, latest_hash, name);
+
+    // Version change
+    let old_version = if addon.version.is_empty() {
+        "unknown".to_string()
+    } else {
+        addon.version.clone()
+    };
+    let new_version = addon_version_at(clone_dir, latest_hash, name).unwrap_or_default();
+    let new_version_display = if new_version.is_empty() {
+        "unknown".to_string()
+    } else {
+        new_version.clone()
+    };
+    status!(
+        "    Version: {} -> {}",
+        old_version,
+        if new_version_display == old_version {
+            new_version_display.normal()
+        } else {
+            new_version_display.green()
+        }
+    );
+
+    // File changes under the addon's path
+    let mut changes =
+        diff_name_status(clone_dir, &addon.checksum, latest_hash, &new_path).unwrap_or_default();
+    if old_path != new_path {
+        changes.extend(
+            diff_name_status(clone_dir, &addon.checksum, latest_hash, &old_path)
+                .unwrap_or_default(),
+        );
+    }
+    let count = |status: char| changes.iter().filter(|(s, _)| *s == status).count();
+    status!(
+        "    Files: {} added, {} removed, {} changed",
+        count('A'),
+        count('D'),
+        count('M')
+    );
+    for (status, file) in &changes {
+        let line = format!("      {} {}", status, file);
+        match status {
+            'A' => status!("{}", line.green()),
+            'D' => status!("{}", line.red()),
+            _ => status!("{}", line.yellow()),
+        }
+    }
+
+    print_changelog(clone_dir, latest_hash, addon, &new_version);
+}
+
+/// Print the changelog sections between the locked version and the new version
+fn print_changelog(clone_dir: &Path, rev: &str, addon: &Addon, new_version: &str) {
+    let addon_path = provided_addon_path_at(clone_dir, rev, addon.upstream_name());
+    let Some(content) = changelog::load_at(clone_dir, rev, &addon_path) else {
+        return;
+    };
+
+    let from = Version::parse(&addon.version);
+    let mut sections = changelog::between(
+        changelog::parse(&content),
+        from,
+        Version::parse(new_version),
+    );
+    // Without a known installed version, the whole history is not worth printing
+    if from.is_none() {
+        sections.truncate(1);
+    }
+    if sections.is_empty() {
+        return;
+    }
+
+    if changelog::has_breaking_changes(&sections) {
+        status!(
+            "    {}",
+            "This update contains breaking changes!".red().bold()
+        );
+    }
+    status!("    Changelog:");
+    changelog::print_sections(&sections, "      ");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::git_command;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn addon(name: &str, policy: Option<UpgradePolicy>) -> Addon {
+        Addon {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            source: "https://github.com/o/r".to_string(),
+            checksum: String::new(),
+            track: None,
+            transitive: false,
+            dependencies: Vec::new(),
+            upstream_name: None,
+            policy,
+        }
+    }
+
+    /// A repository with one commit of addons/foo per version, tagged `v<version>`
+    /// unless the version ends with `!`. Returns its directory and the commits.
+    fn repo(name: &str, versions: &[&str]) -> (PathBuf, Vec<String>) {
+        let dir = std::env::temp_dir().join(format!("gdam-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("addons/foo")).unwrap();
+        let git = |args: &[&str]| {
+            let output = git_command()
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .current_dir(&dir)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?}", args);
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+
+        git(&["init", "-q"]);
+        let mut commits = Vec::new();
+        for version in versions {
+            let (version, tagged) = match version.strip_suffix('!') {
+                Some(version) => (version, false),
+                None => (*version, true),
+            };
+            fs::write(
+                dir.join("addons/foo/plugin.cfg"),
+                format!("[plugin]\nversion=\"{}\"\n", version),
+            )
+            .unwrap();
+            git(&["add", "-A"]);
+            git(&["commit", "-qm", version]);
+            if tagged {
+                git(&["tag", &format!("v{}", version)]);
+            }
+            commits.push(git(&["rev-parse", "HEAD"]));
+        }
+        (dir, commits)
+    }
+
+    #[test]
+    fn strictest_policy_limits_the_group() {
+        let group = [
+            addon("a", None),
+            addon("b", Some(UpgradePolicy::Major)),
+            addon("c", Some(UpgradePolicy::Minor)),
+            addon("d", Some(UpgradePolicy::Minor)),
+        ];
+        assert_eq!(strictest_policy(&group, &group[0]).name, "c");
+        // The lead wins ties
+        assert_eq!(strictest_policy(&group, &group[3]).name, "d");
+        assert_eq!(strictest_policy(&group[..1], &group[0]).name, "a");
+
+        let pinned = [addon("a", Some(UpgradePolicy::Pinned)), addon("b", None)];
+        assert_eq!(strictest_policy(&pinned, &pinned[1]).name, "b");
+    }
+
+    #[test]
+    fn policies_block_commits() {
+        let (dir, commits) = repo("policy", &["1.0.0", "1.0.5", "1.1.0", "2.0.0", "2.1.0!"]);
+        let blocks = |addon: &Addon, commit: usize, named| {
+            policy_blocks(&dir, addon, &commits[commit], named).unwrap()
+        };
+
+        let mut patch = addon("foo", Some(UpgradePolicy::Patch));
+        patch.checksum = commits[0].clone();
+        assert_eq!(blocks(&patch, 1, false), None);
+        assert_eq!(
+            blocks(&patch, 2, false).as_deref(),
+            Some("1.1.0 exceeds patch policy")
+        );
+
+        let mut minor = addon("foo", Some(UpgradePolicy::Minor));
+        minor.checksum = commits[0].clone();
+        assert_eq!(blocks(&minor, 2, false), None);
+        assert_eq!(
+            blocks(&minor, 3, false).as_deref(),
+            Some("2.0.0 exceeds minor policy")
+        );
+        // Untagged commits are compared by their plugin.cfg version
+        assert_eq!(
+            blocks(&minor, 4, false).as_deref(),
+            Some("2.1.0 exceeds minor policy")
+        );
+
+        minor.version = String::new();
+        minor.checksum = commits[4].clone();
+        assert_eq!(
+            blocks(&minor, 2, false).as_deref(),
+            Some("installed version unknown, required by minor policy")
+        );
+
+        let pinned = addon("foo", Some(UpgradePolicy::Pinned));
+        assert_eq!(blocks(&pinned, 3, false).as_deref(), Some("pinned"));
+        assert_eq!(blocks(&pinned, 3, true), None);
+
+        let mut tracked = addon("foo", None);
+        tracked.track = Some("v1.*".to_string());
+        assert_eq!(blocks(&tracked, 2, false), None);
+        assert_eq!(
+            blocks(&tracked, 3, false).as_deref(),
+            Some("not on its track 'v1.*'")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
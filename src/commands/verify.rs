@@ -0,0 +1,167 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::{ADDONS_DIR, Config};
+use crate::error::{Error, Result};
+use crate::git::{ensure_repo, is_ignored, list_files_at, repo_cache_dir, rev_parse, short_hash};
+use crate::output;
+use crate::package::{compare_addon_files, hash_addon_files, manifest_at, provided_addon_path_at};
+use crate::project::Project;
+use crate::utils::get_cache_dir;
+
+#[derive(Debug, Serialize)]
+struct VerifyOutput {
+    ok: bool,
+    addons: Vec<VerifiedAddon>,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifiedAddon {
+    name: String,
+    commit: String,
+    ok: bool,
+    problems: Vec<Problem>,
+}
+
+/// A difference between the files on disk and the locked commit
+#[derive(Debug, Serialize)]
+struct Problem {
+    /// `missing`, `added`, `deleted`, `modified` or `ignored`
+    kind: &'static str,
+    path: String,
+}
+
+impl Problem {
+    fn describe(&self) -> String {
+        match self.kind {
+            "missing" => format!("{} is missing", self.path),
+            "added" => format!("A {} (only on disk)", self.path),
+            "deleted" => format!("D {} (missing on disk)", self.path),
+            "ignored" => format!("{} is ignored by .gitignore", self.path),
+            _ => format!("M {}", self.path),
+        }
+    }
+}
+
+/// Check that the files in addons/ are identical to the locked commits,
+/// returns whether they all are
+pub fn execute(project: &Project) -> Result<bool> {
+    if !Config::exists(project) {
+        return Err(Error::ConfigNotFound);
+    }
+
+    let config = Config::load(project)?;
+    if config.addons.is_empty() {
+        if output::is_json() {
+            output::print_json(&VerifyOutput {
+                ok: true,
+                addons: Vec::new(),
+            })?;
+        } else {
+            status!("No addons to verify.");
+        }
+        return Ok(true);
+    }
+
+    let cache_dir = get_cache_dir()?;
+    let mut verified = Vec::new();
+
+    for addon in &config.addons {
+        let relative_dir = format!("{}/{}", ADDONS_DIR, addon.name);
+        let addon_dir = project.path(&relative_dir);
+        let mut problems = Vec::new();
+
+        if !addon_dir.is_dir() {
+            problems.push(Problem {
+                kind: "missing",
+                path: relative_dir.clone(),
+            });
+        } else {
+            let clone_dir = repo_cache_dir(&cache_dir, &addon.source)?;
+
+            // Only fetch when the locked commit is not in the cache yet
+            if rev_parse(&clone_dir, &addon.checksum).is_err() {
+                status!("Fetching {}...", addon.source);
+                ensure_repo(project, &addon.source, &clone_dir)?;
+            }
+
+            let path = provided_addon_path_at(&clone_dir, &addon.checksum, addon.upstream_name());
+            let upstream = list_files_at(&clone_dir, &addon.checksum, &path)?;
+            let exclude = manifest_at(&clone_dir, &addon.checksum)
+                .map(|m| m.exclude)
+                .unwrap_or_default();
+            let local = hash_addon_files(&addon_dir)?;
+
+            for (status, file) in compare_addon_files(&local, &upstream, &exclude) {
+                problems.push(Problem {
+                    kind: match status {
+                        'A' => "added",
+                        'D' => "deleted",
+                        _ => "modified",
+                    },
+                    path: file,
+                });
+            }
+
+            // Vendored addons must be committed
+            if config.vendor && is_ignored(project.root(), &format!("{}/", relative_dir)) {
+                problems.push(Problem {
+                    kind: "ignored",
+                    path: relative_dir.clone(),
+                });
+            }
+        }
+
+        if problems.is_empty() {
+            status!(
+                "  {} {} {}",
+                addon.name.green(),
+                format!("({})", short_hash(&addon.checksum)).dimmed(),
+                "ok".green()
+            );
+        } else {
+            status!(
+                "  {} {} {}",
+                addon.name.red(),
+                format!("({})", short_hash(&addon.checksum)).dimmed(),
+                "differs".red()
+            );
+            for problem in &problems {
+                status!("    {}", problem.describe().yellow());
+            }
+        }
+        verified.push(VerifiedAddon {
+            name: addon.name.clone(),
+            commit: addon.checksum.clone(),
+            ok: problems.is_empty(),
+            problems,
+        });
+    }
+
+    let failed = verified.iter().filter(|a| !a.ok).count();
+    if output::is_json() {
+        output::print_json(&VerifyOutput {
+            ok: failed == 0,
+            addons: verified,
+        })?;
+        return Ok(failed == 0);
+    }
+
+    if failed > 0 {
+        eprintln!(
+            "\n{}",
+            format!(
+                "{} addon(s) failed verification. Run 'gdam install' to restore the locked files",
+                failed
+            )
+            .red()
+        );
+        return Ok(false);
+    }
+
+    status!(
+        "\n{}",
+        format!("All {} addon(s) match gdam.yaml.", config.addons.len()).green()
+    );
+    Ok(true)
+}
@@ -1,8 +1,19 @@
+use serde::Serialize;
+
 use crate::error::Result;
+use crate::output;
+
+#[derive(Serialize)]
+struct VersionOutput {
+    version: &'static str,
+}
 
 pub fn execute() -> Result<()> {
     // TODO: Implement version command
     let version = env!("CARGO_PKG_VERSION");
-    println!("godot-addon-manager version: v{}", version);
+    if output::is_json() {
+        return output::print_json(&VersionOutput { version });
+    }
+    status!("godot-addon-manager version: v{}", version);
     Ok(())
 }
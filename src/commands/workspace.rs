@@ -0,0 +1,374 @@
+use std::path::Path;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::commands::install::{self, InstallOptions};
+use crate::commands::upgrade::{self, UpgradeOptions, UpgradePreview, policy_blocks};
+use crate::config::{Addon, Config};
+use crate::error::{Error, Result};
+use crate::git::{commit_timestamp, is_ancestor, repo_cache_dir, same_source, short_hash};
+use crate::package::addon_version_at;
+use crate::project::Project;
+use crate::utils::get_cache_dir;
+use crate::workspace::{WORKSPACE_FILE, Workspace};
+
+/// A member project and its directory relative to the workspace root
+struct Member {
+    name: String,
+    project: Project,
+}
+
+/// Install addons in every member of a workspace.
+/// An addon installed from a URL is shared with all members.
+pub fn install(root: &Path, git_url: Option<String>, options: InstallOptions) -> Result<()> {
+    let mut workspace = Workspace::load(root)?;
+    let members = load_members(root, &workspace)?;
+
+    let Some(url) = git_url else {
+        for member in &members {
+            print_header(member);
+            share_addons(&workspace, member, true)?;
+            install::execute(&member.project, None, InstallOptions::default())?;
+        }
+        warn_diverging(&members)?;
+        return Ok(());
+    };
+
+    // Install in the first member, then share what it added to gdam.yaml
+    let (first, others) = members.split_first().expect("members is not empty");
+    print_header(first);
+    share_addons(&workspace, first, true)?;
+    let before = Config::load(&first.project)?.addons;
+    install::execute(&first.project, Some(url), options)?;
+
+    // Dependencies installed along the way are shared as well
+    let added: Vec<Addon> = Config::load(&first.project)?
+        .addons
+        .into_iter()
+        .filter(|a| {
+            !before
+                .iter()
+                .any(|b| b.name == a.name && b.source == a.source && b.checksum == a.checksum)
+        })
+        .collect();
+
+    if added.is_empty() {
+        return Ok(());
+    }
+    for addon in added {
+        workspace.addons.retain(|a| a.name != addon.name);
+        workspace.addons.push(addon);
+    }
+    workspace.save(root)?;
+    status!("\nShared in {}", WORKSPACE_FILE);
+
+    for member in others {
+        print_header(member);
+        share_addons(&workspace, member, true)?;
+        install::execute(&member.project, None, InstallOptions::default())?;
+    }
+    Ok(())
+}
+
+/// What a dry run would change in each member
+#[derive(Serialize)]
+pub struct WorkspacePreview {
+    members: Vec<MemberPreview>,
+}
+
+#[derive(Serialize)]
+struct MemberPreview {
+    member: String,
+    #[serde(flatten)]
+    preview: UpgradePreview,
+}
+
+/// Upgrade addons in every member of a workspace, then move each source to the
+/// newest commit any member upgraded it to. Returns what a dry run would change.
+pub fn upgrade(
+    root: &Path,
+    addon_name: Option<String>,
+    options: UpgradeOptions,
+) -> Result<WorkspacePreview> {
+    let mut workspace = Workspace::load(root)?;
+    let members = load_members(root, &workspace)?;
+    let mut preview = WorkspacePreview {
+        members: Vec::new(),
+    };
+
+    let mut upgraded = 0;
+    for member in &members {
+        print_header(member);
+        if !Config::exists(&member.project) {
+            status!("{}", "No gdam.yaml, skipped".dimmed());
+            continue;
+        }
+        if share_addons(&workspace, member, false)? {
+            install::execute(&member.project, None, InstallOptions::default())?;
+        }
+        if let Some(name) = &addon_name
+            && !Config::load(&member.project)?
+                .addons
+                .iter()
+                .any(|a| a.name == *name)
+        {
+            status!("{}", format!("{} is not installed, skipped", name).dimmed());
+            continue;
+        }
+        upgraded += 1;
+        let changes = upgrade::execute(
+            &member.project,
+            addon_name.clone(),
+            UpgradeOptions {
+                dry_run: options.dry_run,
+                confirm: options.confirm,
+                to: options.to.clone(),
+            },
+        )?;
+        preview.members.push(MemberPreview {
+            member: member.name.clone(),
+            preview: changes,
+        });
+    }
+
+    if let Some(name) = &addon_name
+        && upgraded == 0
+    {
+        return Err(Error::AddonNotFound(format!(
+            "'{}' in the gdam.yaml of any member",
+            name
+        )));
+    }
+    if options.dry_run {
+        return Ok(preview);
+    }
+
+    align_members(&members, addon_name.as_deref())?;
+
+    // Lock the shared addons to what the members now use
+    let configs = load_configs(&members)?;
+    let mut changed = false;
+    for shared in &mut workspace.addons {
+        let Some(locked) = configs
+            .iter()
+            .find_map(|(_, c)| c.addons.iter().find(|a| a.name == shared.name))
+        else {
+            continue;
+        };
+        if locked.checksum != shared.checksum {
+            shared.checksum = locked.checksum.clone();
+            shared.version = locked.version.clone();
+            changed = true;
+        }
+    }
+    if changed {
+        workspace.save(root)?;
+        status!("\nUpdated the shared addons in {}", WORKSPACE_FILE);
+    }
+    Ok(preview)
+}
+
+/// List the member projects, failing if there are none
+fn load_members(root: &Path, workspace: &Workspace) -> Result<Vec<Member>> {
+    let members: Vec<Member> = workspace
+        .member_dirs(root)?
+        .into_iter()
+        .map(|dir| Member {
+            name: dir.display().to_string(),
+            project: Project::at(root.join(dir)),
+        })
+        .collect();
+    if members.is_empty() {
+        return Err(Error::Other(format!(
+            "No member projects found, list them under 'members' in {}",
+            WORKSPACE_FILE
+        )));
+    }
+    Ok(members)
+}
+
+/// Print the header naming the member the following output is about
+fn print_header(member: &Member) {
+    status!("\n{}", format!("==> {}", member.name).bold());
+}
+
+/// Add the shared addons to the gdam.yaml of a member. Returns whether it changed.
+fn share_addons(workspace: &Workspace, member: &Member, create: bool) -> Result<bool> {
+    let project = &member.project;
+    if !Config::exists(project) {
+        if !create {
+            return Ok(false);
+        }
+        Config::default().save(project)?;
+        status!("Created gdam.yaml");
+    }
+    let mut config = Config::load(project)?;
+    let changed = workspace.apply_shared(&mut config, &member.name)?;
+    if changed {
+        config.save(project)?;
+    }
+    Ok(changed)
+}
+
+/// Load the config of every member that has one
+fn load_configs(members: &[Member]) -> Result<Vec<(&Member, Config)>> {
+    let mut configs = Vec::new();
+    for member in members {
+        if Config::exists(&member.project) {
+            configs.push((member, Config::load(&member.project)?));
+        }
+    }
+    Ok(configs)
+}
+
+/// Group the locked commits of each source across members
+fn commits_by_source(configs: &[(&Member, Config)]) -> Vec<(String, Vec<String>)> {
+    let mut sources: Vec<(String, Vec<String>)> = Vec::new();
+    for (_, config) in configs {
+        for addon in &config.addons {
+            let index = match sources
+                .iter()
+                .position(|(s, _)| same_source(s, &addon.source))
+            {
+                Some(index) => index,
+                None => {
+                    sources.push((addon.source.clone(), Vec::new()));
+                    sources.len() - 1
+                }
+            };
+            let commits = &mut sources[index].1;
+            if !commits.contains(&addon.checksum) {
+                commits.push(addon.checksum.clone());
+            }
+        }
+    }
+    sources
+}
+
+/// Warn about sources the members lock to different commits
+fn warn_diverging(members: &[Member]) -> Result<()> {
+    let configs = load_configs(members)?;
+    for (source, commits) in commits_by_source(&configs) {
+        if commits.len() > 1 {
+            status!(
+                "\n{}",
+                format!(
+                    "Warning: members lock {} to {} different commits, run 'gdam upgrade' at the workspace root to align them",
+                    source,
+                    commits.len()
+                )
+                .yellow()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Move every member to the newest commit locked for each source, or only for
+/// the source of the named addon, and reinstall the members that changed
+fn align_members(members: &[Member], addon_name: Option<&str>) -> Result<()> {
+    let mut configs = load_configs(members)?;
+    let cache_dir = get_cache_dir()?;
+    let mut changed_members: Vec<usize> = Vec::new();
+
+    for (source, commits) in commits_by_source(&configs) {
+        if commits.len() < 2 {
+            continue;
+        }
+        if let Some(name) = addon_name
+            && !configs.iter().any(|(_, c)| {
+                c.addons
+                    .iter()
+                    .any(|a| a.name == name && same_source(&a.source, &source))
+            })
+        {
+            continue;
+        }
+        let clone_dir = repo_cache_dir(&cache_dir, &source)?;
+        // A descendant is newer, unrelated commits compare by commit time
+        let mut newest: Option<(String, i64)> = None;
+        for commit in &commits {
+            let timestamp = commit_timestamp(&clone_dir, commit)?;
+            let newer = match &newest {
+                None => true,
+                Some((n, _)) if is_ancestor(&clone_dir, n, commit) => true,
+                Some((n, _)) if is_ancestor(&clone_dir, commit, n) => false,
+                Some((_, t)) => timestamp > *t,
+            };
+            if newer {
+                newest = Some((commit.clone(), timestamp));
+            }
+        }
+        let Some((newest, _)) = newest else {
+            continue;
+        };
+
+        let mut aligned = Vec::new();
+        let mut kept = Vec::new();
+        for (i, (member, config)) in configs.iter_mut().enumerate() {
+            let mut changed = false;
+            for addon in &mut config.addons {
+                if same_source(&addon.source, &source) && addon.checksum != newest {
+                    let named = addon_name == Some(addon.name.as_str());
+                    if let Some(reason) = policy_blocks(&clone_dir, addon, &newest, named)? {
+                        kept.push(format!(
+                            "{} in {} at {} ({})",
+                            addon.name,
+                            member.name,
+                            short_hash(&addon.checksum),
+                            reason
+                        ));
+                        continue;
+                    }
+                    addon.checksum = newest.clone();
+                    if let Some(version) =
+                        addon_version_at(&clone_dir, &newest, addon.upstream_name())
+                    {
+                        addon.version = version;
+                    }
+                    changed = true;
+                }
+            }
+            if changed {
+                aligned.push(member.name.as_str());
+                if !changed_members.contains(&i) {
+                    changed_members.push(i);
+                }
+            }
+        }
+        if !aligned.is_empty() {
+            status!(
+                "\n{}",
+                format!(
+                    "Aligned {} to {} in {}",
+                    source,
+                    short_hash(&newest),
+                    aligned.join(", ")
+                )
+                .green()
+            );
+        }
+        if !kept.is_empty() {
+            status!(
+                "\n{}",
+                format!(
+                    "Members still lock {} to different commits, kept by upgrade policy:",
+                    source
+                )
+                .yellow()
+            );
+            for addon in &kept {
+                status!("  {}", addon.yellow());
+            }
+        }
+    }
+
+    for i in changed_members {
+        let (member, config) = &configs[i];
+        config.save(&member.project)?;
+        print_header(member);
+        install::execute(&member.project, None, InstallOptions::default())?;
+    }
+    Ok(())
+}
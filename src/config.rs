@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
 
 use crate::error::{Error, Result};
+use crate::git::same_source;
+use crate::project::Project;
+use crate::settings;
 
 pub const CONFIG_FILE: &str = "gdam.yaml";
 pub const ADDONS_DIR: &str = "addons";
@@ -10,6 +14,22 @@ pub const ADDONS_DIR: &str = "addons";
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub version: u32,
+    /// Commit addons to the repository instead of ignoring them in .gitignore
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub vendor: bool,
+    /// Registry index files or git repositories used to resolve short addon names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<String>,
+    /// Custom shorthand source schemes, e.g. `gt: https://git.example.com/{}`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub source_schemes: BTreeMap<String, String>,
+    /// URL prefixes replaced when fetching, like git's `insteadOf`,
+    /// e.g. `https://github.com/: https://gitea.example.com/github/`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rewrites: BTreeMap<String, String>,
+    /// Fallback URLs of a source, tried in order when fetching it fails
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mirrors: BTreeMap<String, Vec<String>>,
     pub addons: Vec<Addon>,
 }
 
@@ -19,42 +39,175 @@ pub struct Addon {
     pub version: String,
     pub source: String,
     pub checksum: String,
+    /// Branch or tag series followed on upgrade (default branch if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<String>,
+    /// Installed as a dependency of another addon rather than requested directly
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub transitive: bool,
+    /// Sources this addon depends on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    /// Folder name in the source repository, if installed under another name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_name: Option<String>,
+    /// How far `gdam upgrade` may move this addon (latest commit if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<UpgradePolicy>,
+}
+
+/// Limits applied to an addon by `gdam upgrade`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpgradePolicy {
+    /// Only upgraded when named explicitly
+    Pinned,
+    /// Only patch releases of the installed major.minor version
+    Patch,
+    /// Only minor and patch releases of the installed major version
+    Minor,
+    /// Any newer release
+    Major,
+    /// Always the latest commit of the branch, ignoring tags
+    TrackBranch,
+}
+
+impl fmt::Display for UpgradePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            UpgradePolicy::Pinned => "pinned",
+            UpgradePolicy::Patch => "patch",
+            UpgradePolicy::Minor => "minor",
+            UpgradePolicy::Major => "major",
+            UpgradePolicy::TrackBranch => "track-branch",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Addon {
+    /// Get the folder name of the addon in its source repository
+    pub fn upstream_name(&self) -> &str {
+        self.upstream_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             version: 1,
+            vendor: false,
+            registries: Vec::new(),
+            source_schemes: BTreeMap::new(),
+            rewrites: BTreeMap::new(),
+            mirrors: BTreeMap::new(),
             addons: Vec::new(),
         }
     }
 }
 
 impl Config {
-    /// Check if config file exists in current directory
-    pub fn exists() -> bool {
-        Path::new(CONFIG_FILE).exists()
+    /// Check if the project has a config file
+    pub fn exists(project: &Project) -> bool {
+        project.config_file().exists()
     }
 
     /// Load config from file
-    pub fn load() -> Result<Self> {
-        let content = fs::read_to_string(CONFIG_FILE)?;
-        serde_yaml::from_str(&content)
-            .map_err(|e| Error::Other(format!("Failed to parse config: {}", e)))
+    pub fn load(project: &Project) -> Result<Self> {
+        let content = fs::read_to_string(project.config_file())?;
+        serde_yaml::from_str(&content).map_err(|e| Error::yaml(CONFIG_FILE, e))
     }
 
     /// Save config to file
-    pub fn save(&self) -> Result<()> {
+    pub fn save(&self, project: &Project) -> Result<()> {
         let content = serde_yaml::to_string(self)
             .map_err(|e| Error::Other(format!("Failed to serialize config: {}", e)))?;
-        fs::write(CONFIG_FILE, content)?;
+        fs::write(project.config_file(), content)?;
         Ok(())
     }
+
+    /// Registries used to resolve short addon names: those of the project, or
+    /// the default registry of the user settings
+    pub fn effective_registries(&self) -> Vec<String> {
+        if !self.registries.is_empty() {
+            return self.registries.clone();
+        }
+        settings::current()
+            .registry()
+            .map(|r| vec![r.to_string()])
+            .unwrap_or_default()
+    }
+
+    /// Group addons by source, in the order each source first appears
+    pub fn addons_by_source(&self) -> Vec<Vec<&Addon>> {
+        let mut groups: Vec<Vec<&Addon>> = Vec::new();
+        for addon in &self.addons {
+            match groups
+                .iter_mut()
+                .find(|g| same_source(&g[0].source, &addon.source))
+            {
+                Some(group) => group.push(addon),
+                None => groups.push(vec![addon]),
+            }
+        }
+        groups
+    }
+
+    /// Get addons (other than those from `source`) that depend on `source`
+    pub fn dependents_of(&self, source: &str) -> Vec<&Addon> {
+        self.addons
+            .iter()
+            .filter(|a| {
+                !same_source(&a.source, source)
+                    && a.dependencies.iter().any(|d| same_source(d, source))
+            })
+            .collect()
+    }
+
+    /// Remove transitive dependencies no longer required by any remaining addon.
+    /// Returns the removed addons.
+    pub fn prune_dependencies(&mut self) -> Vec<Addon> {
+        // Walk the dependency graph from directly installed addons
+        let mut needed: Vec<&str> = self
+            .addons
+            .iter()
+            .filter(|a| !a.transitive)
+            .map(|a| a.source.as_str())
+            .collect();
+        let mut i = 0;
+        while i < needed.len() {
+            let source = needed[i];
+            for addon in self
+                .addons
+                .iter()
+                .filter(|a| same_source(&a.source, source))
+            {
+                for dep in &addon.dependencies {
+                    if !needed.iter().any(|n| same_source(n, dep)) {
+                        needed.push(dep);
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        let needed: Vec<String> = needed.into_iter().map(String::from).collect();
+        let (kept, removed) = self
+            .addons
+            .drain(..)
+            .partition(|a| !a.transitive || needed.iter().any(|n| same_source(n, &a.source)));
+        self.addons = kept;
+        removed
+    }
 }
 
 /// Get list of existing addon folders
-pub fn get_existing_addons() -> Result<Vec<String>> {
-    let addons_path = Path::new(ADDONS_DIR);
+pub fn get_existing_addons(project: &Project) -> Result<Vec<String>> {
+    let addons_path = project.addons_dir();
     if !addons_path.exists() {
         return Ok(Vec::new());
     }
@@ -62,11 +215,93 @@ pub fn get_existing_addons() -> Result<Vec<String>> {
     let mut addons = Vec::new();
     for entry in fs::read_dir(addons_path)? {
         let entry = entry?;
-        if entry.file_type()?.is_dir() {
-            if let Some(name) = entry.file_name().to_str() {
-                addons.push(name.to_string());
-            }
+        if entry.file_type()?.is_dir()
+            && let Some(name) = entry.file_name().to_str()
+        {
+            addons.push(name.to_string());
         }
     }
     Ok(addons)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> Config {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn names(addons: &[Addon]) -> Vec<&str> {
+        addons.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    // dialog needs uikit, which needs utilx. The URLs are spelled differently
+    // on purpose, dependencies match by normalized source.
+    const DIALOG: &str = "version: 1
+addons:
+- name: dialog
+  version: '1.0'
+  source: https://github.com/o/dialog
+  checksum: a
+  dependencies: [git@github.com:o/uikit.git]
+- name: uikit
+  version: '1.0'
+  source: https://github.com/o/uikit
+  checksum: b
+  transitive: true
+  dependencies: [https://github.com/o/utilx.git]
+- name: utilx
+  version: '1.0'
+  source: https://github.com/o/utilx
+  checksum: c
+  transitive: true
+- name: orphan
+  version: '1.0'
+  source: https://github.com/o/orphan
+  checksum: d
+  transitive: true
+";
+
+    #[test]
+    fn prune_keeps_dependency_chains() {
+        let mut config = config(DIALOG);
+        let removed = config.prune_dependencies();
+        assert_eq!(names(&removed), ["orphan"]);
+        assert_eq!(names(&config.addons), ["dialog", "uikit", "utilx"]);
+    }
+
+    #[test]
+    fn prune_after_uninstall() {
+        let mut config = config(DIALOG);
+        config.addons.retain(|a| a.name != "dialog");
+        let removed = config.prune_dependencies();
+        assert_eq!(names(&removed), ["uikit", "utilx", "orphan"]);
+        assert!(config.addons.is_empty());
+    }
+
+    #[test]
+    fn prune_keeps_direct_installs() {
+        let mut config = config(DIALOG);
+        config.addons.retain(|a| a.name != "dialog");
+        config.addons[1].transitive = false;
+        let removed = config.prune_dependencies();
+        assert_eq!(names(&removed), ["uikit", "orphan"]);
+        assert_eq!(names(&config.addons), ["utilx"]);
+    }
+
+    #[test]
+    fn dependents() {
+        let config = config(DIALOG);
+        let dependents = |source| {
+            config
+                .dependents_of(source)
+                .into_iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(dependents("git@github.com:o/uikit.git"), ["dialog"]);
+        assert_eq!(dependents("https://github.com/o/utilx"), ["uikit"]);
+        assert!(dependents("https://github.com/o/dialog").is_empty());
+    }
+}
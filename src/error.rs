@@ -1,4 +1,5 @@
 use std::fmt;
+use std::path::PathBuf;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -8,12 +9,29 @@ pub enum Error {
     NotInProject,
     /// Config file not found
     ConfigNotFound,
+    /// A YAML or JSON file could not be parsed
+    ConfigParse {
+        file: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
     /// Invalid URL format
     InvalidUrl(String),
-    /// Addon not found in repository
+    /// Addon not installed in the project, or not listed in its registries
     AddonNotFound(String),
+    /// The repository doesn't provide the addon
+    AddonMissingInRepo(String),
+    /// Branch, tag or commit missing from the repository
+    RefNotFound(String),
+    /// The remote could not be reached
+    Network { url: String, message: String },
+    /// The remote refused the credentials, or asked for some
+    Auth { message: String, hint: String },
+    /// A cached repository gdam cannot use as is
+    DirtyCache { path: PathBuf, message: String },
     /// Git operation failed
-    GitError(String),
+    Git(String),
     /// IO error
     Io(std::io::Error),
     /// Other errors
@@ -24,16 +42,134 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::NotInProject => write!(f, "Not in a Godot project directory"),
-            Error::ConfigNotFound => write!(f, "gdam.yaml not found. Run 'gdam init' first"),
+            Error::ConfigNotFound => write!(f, "gdam.yaml not found"),
+            Error::ConfigParse { file, message, .. } => {
+                write!(f, "Failed to parse {}: {}", file, message)
+            }
             Error::InvalidUrl(url) => write!(f, "Invalid URL: {}", url),
             Error::AddonNotFound(msg) => write!(f, "Addon not found: {}", msg),
-            Error::GitError(msg) => write!(f, "Git error: {}", msg),
+            Error::AddonMissingInRepo(msg) => write!(f, "Addon not found: {}", msg),
+            Error::RefNotFound(reference) => write!(f, "Ref '{}' not found", reference),
+            Error::Network { url, message } => write!(f, "Cannot reach {}: {}", url, message),
+            Error::Auth { message, .. } => write!(f, "Authentication error: {}", message),
+            Error::DirtyCache { path, message } => write!(
+                f,
+                "Cached repository {} is in an unexpected state: {}",
+                path.display(),
+                message
+            ),
+            Error::Git(msg) => write!(f, "Git error: {}", msg),
             Error::Io(err) => write!(f, "IO error: {}", err),
             Error::Other(msg) => write!(f, "{}", msg),
         }
     }
 }
 
+impl Error {
+    /// Parse error of a YAML file, with the position serde_yaml reports
+    pub fn yaml(file: impl fmt::Display, err: serde_yaml::Error) -> Self {
+        let location = err.location();
+        Error::ConfigParse {
+            file: file.to_string(),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            message: err.to_string(),
+        }
+    }
+
+    /// Parse error of a JSON file
+    pub fn json(file: impl fmt::Display, err: serde_json::Error) -> Self {
+        Error::ConfigParse {
+            file: file.to_string(),
+            line: Some(err.line()),
+            column: Some(err.column()),
+            message: err.to_string(),
+        }
+    }
+
+    /// Stable name of the error, used in JSON output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::NotInProject => "not_in_project",
+            Error::ConfigNotFound => "config_not_found",
+            Error::ConfigParse { .. } => "config_parse",
+            Error::InvalidUrl(_) => "invalid_url",
+            Error::AddonNotFound(_) => "addon_not_found",
+            Error::AddonMissingInRepo(_) => "addon_missing_in_repo",
+            Error::RefNotFound(_) => "ref_not_found",
+            Error::Network { .. } => "network",
+            Error::Auth { .. } => "auth",
+            Error::DirtyCache { .. } => "dirty_cache",
+            Error::Git(_) => "git",
+            Error::Io(_) => "io",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// Exit code of the process. Codes are stable, see the Exit Codes page of the docs.
+    /// 2 is left to command line usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) | Error::Other(_) => 1,
+            Error::NotInProject => 3,
+            Error::ConfigNotFound => 4,
+            Error::ConfigParse { .. } => 5,
+            Error::InvalidUrl(_) => 6,
+            Error::AddonNotFound(_) => 7,
+            Error::AddonMissingInRepo(_) => 8,
+            Error::RefNotFound(_) => 9,
+            Error::Network { .. } => 10,
+            Error::Auth { .. } => 11,
+            Error::DirtyCache { .. } => 12,
+            Error::Git(_) => 13,
+        }
+    }
+
+    /// What the user can do about the error
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            Error::NotInProject => {
+                "Run gdam in a directory with project.godot, or point to one with -C <dir>"
+                    .to_string()
+            }
+            Error::ConfigNotFound => "Run 'gdam init' to create it".to_string(),
+            Error::ConfigParse {
+                file,
+                line: Some(line),
+                column: Some(column),
+                ..
+            } => format!("Fix {} at line {}, column {}", file, line, column),
+            Error::ConfigParse { file, .. } => format!("Fix the syntax of {}", file),
+            Error::InvalidUrl(_) => {
+                "Use an https, ssh or git URL, a shorthand like gh:owner/repo, or a name listed in a registry"
+                    .to_string()
+            }
+            Error::AddonNotFound(_) => {
+                "Run 'gdam list' to see installed addons, or 'gdam search' to look in the registries"
+                    .to_string()
+            }
+            Error::AddonMissingInRepo(_) => {
+                "Check the repository has the addon under addons/, or declares it in gdam-package.yaml"
+                    .to_string()
+            }
+            Error::RefNotFound(_) => {
+                "Check the branch, tag or commit exists in the source repository".to_string()
+            }
+            Error::Network { .. } => {
+                "Check your network connection, or add 'mirrors' or 'rewrites' for the source"
+                    .to_string()
+            }
+            Error::Auth { hint, .. } => hint.clone(),
+            Error::DirtyCache { path, .. } => format!(
+                "Run 'gdam cache clear', or remove {} to fetch it again",
+                path.display()
+            ),
+            Error::Git(_) | Error::Io(_) | Error::Other(_) => return None,
+        };
+        Some(hint)
+    }
+}
+
 impl std::error::Error for Error {}
 
 impl From<std::io::Error> for Error {
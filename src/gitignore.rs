@@ -0,0 +1,473 @@
+use std::fs;
+
+use crate::config::{ADDONS_DIR, Config};
+use crate::error::Result;
+use crate::project::Project;
+
+pub const GITIGNORE_FILE: &str = ".gitignore";
+
+/// Lines delimiting the part of .gitignore rewritten by gdam
+const BLOCK_BEGIN: &str = "# BEGIN gdam: managed addons, rewritten on install and uninstall";
+const BLOCK_END: &str = "# END gdam";
+
+/// A pattern of a .gitignore file
+#[derive(Debug)]
+struct Rule {
+    pattern: String,
+    /// `!pattern`, re-includes what earlier patterns excluded
+    negated: bool,
+    /// `pattern/`, only matches directories
+    dir_only: bool,
+    /// Contains a slash before the end, matched against the whole path
+    /// instead of the name at any depth
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        // Trailing spaces are ignored unless escaped with `\`
+        let bytes = line.as_bytes();
+        let mut end = line.len();
+        while end > 0 && bytes[end - 1] == b' ' && !(end >= 2 && bytes[end - 2] == b'\\') {
+            end -= 1;
+        }
+        let line = &line[..end];
+
+        // `\!` and `\#` stay escaped and match literally
+        let (line, negated) = match line.strip_prefix('!') {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        let (line, dir_only) = match line.strip_suffix('/') {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        if line.is_empty() {
+            return None;
+        }
+
+        let anchored = line.contains('/');
+        Some(Rule {
+            pattern: line.trim_start_matches('/').to_string(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            wildmatch(self.pattern.as_bytes(), path.as_bytes())
+        } else {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            wildmatch(self.pattern.as_bytes(), name.as_bytes())
+        }
+    }
+}
+
+/// The patterns of a .gitignore file at the project root
+#[derive(Debug)]
+pub struct Gitignore {
+    rules: Vec<Rule>,
+}
+
+impl Gitignore {
+    pub fn parse(content: &str) -> Self {
+        Gitignore {
+            rules: content.lines().filter_map(Rule::parse).collect(),
+        }
+    }
+
+    /// Check if a path relative to the project root is ignored. As in git, nothing
+    /// inside an ignored directory can be re-included.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let path = path.trim_matches('/');
+        let mut end = 0;
+        loop {
+            let next = path[end..].find('/').map(|i| end + i);
+            let prefix = &path[..next.unwrap_or(path.len())];
+            let prefix_is_dir = next.is_some() || is_dir;
+
+            // The last matching pattern decides
+            let ignored = self
+                .rules
+                .iter()
+                .rev()
+                .find(|r| r.matches(prefix, prefix_is_dir))
+                .is_some_and(|r| !r.negated);
+
+            match next {
+                Some(_) if ignored => return true,
+                Some(i) => end = i + 1,
+                None => return ignored,
+            }
+        }
+    }
+}
+
+/// Match a path against a gitignore pattern: `*` and `?` don't match `/`,
+/// `**` matches across directories, `[a-z]` and `[!a-z]` match character
+/// classes and `\` escapes the next character
+fn wildmatch(p: &[u8], s: &[u8]) -> bool {
+    match p.first() {
+        None => s.is_empty(),
+        Some(b'*') if p.get(1) == Some(&b'*') => {
+            let rest = &p[2..];
+            match rest.strip_prefix(b"/") {
+                // `**/` matches zero or more directories
+                Some(after) => {
+                    wildmatch(after, s)
+                        || s.iter()
+                            .enumerate()
+                            .any(|(i, c)| *c == b'/' && wildmatch(after, &s[i + 1..]))
+                }
+                // A trailing `**` matches everything
+                None if rest.is_empty() => true,
+                None => (0..=s.len()).any(|i| wildmatch(rest, &s[i..])),
+            }
+        }
+        Some(b'*') => (0..=s.len())
+            .take_while(|&i| i == 0 || s[i - 1] != b'/')
+            .any(|i| wildmatch(&p[1..], &s[i..])),
+        Some(b'?') => s.first().is_some_and(|c| *c != b'/') && wildmatch(&p[1..], &s[1..]),
+        Some(b'[') => match (s.first(), match_class(&p[1..], s.first().copied())) {
+            (Some(c), Some((true, rest))) if *c != b'/' => wildmatch(rest, &s[1..]),
+            (_, Some(_)) => false,
+            // An unterminated class is a literal `[`
+            (_, None) => s.first() == Some(&b'[') && wildmatch(&p[1..], &s[1..]),
+        },
+        Some(b'\\') if p.len() > 1 => s.first() == Some(&p[1]) && wildmatch(&p[2..], &s[1..]),
+        Some(c) => s.first() == Some(c) && wildmatch(&p[1..], &s[1..]),
+    }
+}
+
+/// Match a character against a class whose `[` is already consumed, returning
+/// whether it matched and the pattern after `]`, or None if the class is unterminated
+fn match_class(p: &[u8], c: Option<u8>) -> Option<(bool, &[u8])> {
+    let (negated, mut i) = match p.first() {
+        Some(b'!' | b'^') => (true, 1),
+        _ => (false, 0),
+    };
+    let mut matched = false;
+    let mut first = true;
+    while i < p.len() {
+        if p[i] == b']' && !first {
+            return Some((matched != negated, &p[i + 1..]));
+        }
+        first = false;
+        let lo = if p[i] == b'\\' && i + 1 < p.len() {
+            i += 1;
+            p[i]
+        } else {
+            p[i]
+        };
+        if p.get(i + 1) == Some(&b'-') && p.get(i + 2).is_some_and(|h| *h != b']') {
+            let hi = p[i + 2];
+            matched |= c.is_some_and(|c| lo <= c && c <= hi);
+            i += 3;
+        } else {
+            matched |= c == Some(lo);
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Split .gitignore content into the lines outside the gdam block and the
+/// position of the block among them, if there is one. A block whose end line
+/// was removed stops at the first line gdam didn't write.
+fn split_block(content: &str) -> (Vec<&str>, Option<usize>) {
+    let mut outside = Vec::new();
+    let mut position = None;
+    let mut in_block = false;
+    for line in content.lines() {
+        match line {
+            BLOCK_BEGIN => {
+                in_block = true;
+                position.get_or_insert(outside.len());
+            }
+            BLOCK_END => in_block = false,
+            _ if in_block && is_block_entry(line) => {}
+            _ => {
+                in_block = false;
+                outside.push(line);
+            }
+        }
+    }
+    (outside, position)
+}
+
+/// Check if a line is an entry gdam writes in its block, `/addons/<name>/`
+fn is_block_entry(line: &str) -> bool {
+    line.strip_prefix('/')
+        .and_then(|l| l.strip_prefix(ADDONS_DIR))
+        .and_then(|l| l.strip_prefix('/'))
+        .and_then(|l| l.strip_suffix('/'))
+        .is_some_and(|name| !name.is_empty() && !name.contains('/'))
+}
+
+/// Check if a line is one gdam appended before it kept its entries in a block:
+/// `addons/` or `addons/<name>`. Patterns and deeper paths are the user's own.
+fn is_legacy_entry(line: &str, config: &Config) -> bool {
+    let Some(name) = line.strip_prefix(&format!("{}/", ADDONS_DIR)) else {
+        return false;
+    };
+    if name.contains(['/', '*', '?', '[', '\\']) {
+        return false;
+    }
+    if name.is_empty() {
+        // `addons/` was added by `gdam init`, it only goes away in vendor mode
+        return config.vendor;
+    }
+    config.vendor || config.addons.iter().any(|a| a.name == name)
+}
+
+/// Compute the .gitignore content matching the manifest: a block ignoring each
+/// managed addon the rest of the file doesn't already ignore, or no block in vendor mode.
+/// The block keeps its position, a new one is appended.
+pub fn render(content: &str, config: &Config) -> String {
+    let (lines, position) = split_block(content);
+    let position = position.unwrap_or(lines.len());
+    let (before, after): (Vec<&str>, Vec<&str>) = (
+        lines[..position]
+            .iter()
+            .copied()
+            .filter(|line| !is_legacy_entry(line, config))
+            .collect(),
+        lines[position..]
+            .iter()
+            .copied()
+            .filter(|line| !is_legacy_entry(line, config))
+            .collect(),
+    );
+
+    let existing = Gitignore::parse(&[before.as_slice(), after.as_slice()].concat().join("\n"));
+    let entries: Vec<String> = if config.vendor {
+        Vec::new()
+    } else {
+        config
+            .addons
+            .iter()
+            .map(|a| format!("{}/{}", ADDONS_DIR, a.name))
+            .filter(|path| !existing.is_ignored(path, true))
+            .map(|path| format!("/{}/", path))
+            .collect()
+    };
+
+    let mut output: Vec<&str> = before;
+    if !entries.is_empty() {
+        if after.is_empty() {
+            // Appended after one blank line
+            while output.last().is_some_and(|l| l.trim().is_empty()) {
+                output.pop();
+            }
+            if !output.is_empty() {
+                output.push("");
+            }
+        }
+        output.push(BLOCK_BEGIN);
+        output.extend(entries.iter().map(String::as_str));
+        output.push(BLOCK_END);
+    } else if after.first().is_none_or(|l| l.trim().is_empty()) {
+        // Don't leave the blank line that separated the removed block
+        while output.last().is_some_and(|l| l.trim().is_empty()) {
+            output.pop();
+        }
+    }
+    output.extend(after);
+
+    if output.is_empty() {
+        String::new()
+    } else {
+        output.join("\n") + "\n"
+    }
+}
+
+/// Read the project's .gitignore, empty if it has none
+pub fn read(project: &Project) -> Result<String> {
+    let path = project.path(GITIGNORE_FILE);
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    Ok(fs::read_to_string(path)?)
+}
+
+/// Rewrite the gdam block of .gitignore from the manifest, returns whether the file changed
+pub fn sync(project: &Project, config: &Config) -> Result<bool> {
+    let content = read(project)?;
+    let rendered = render(&content, config);
+    let path = project.path(GITIGNORE_FILE);
+    if rendered == content || (rendered.is_empty() && !path.exists()) {
+        return Ok(false);
+    }
+    fs::write(path, rendered)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> Config {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn config_with_gut() -> Config {
+        config("version: 1\naddons:\n- {name: gut, version: '', source: s, checksum: c}\n")
+    }
+
+    fn ignored(content: &str, path: &str, is_dir: bool) -> bool {
+        Gitignore::parse(content).is_ignored(path, is_dir)
+    }
+
+    #[test]
+    fn wildmatch_stars() {
+        assert!(wildmatch(b"*.gd", b"player.gd"));
+        assert!(!wildmatch(b"*.gd", b"scripts/player.gd"));
+        assert!(wildmatch(b"a/**/b", b"a/b"));
+        assert!(wildmatch(b"a/**/b", b"a/x/y/b"));
+        assert!(wildmatch(b"**/b", b"x/y/b"));
+        assert!(wildmatch(b"a/**", b"a/x/y"));
+        assert!(wildmatch(b"a**z", b"a/b/z"));
+        assert!(wildmatch(b"?.gd", b"a.gd"));
+        assert!(!wildmatch(b"a?b", b"a/b"));
+    }
+
+    #[test]
+    fn wildmatch_classes_and_escapes() {
+        assert!(wildmatch(b"[a-c]x", b"bx"));
+        assert!(!wildmatch(b"[a-c]x", b"dx"));
+        assert!(wildmatch(b"[!a-c]x", b"dx"));
+        assert!(wildmatch(b"[^a-c]x", b"dx"));
+        assert!(!wildmatch(b"[!a-c]x", b"ax"));
+        assert!(!wildmatch(b"[!a]", b"/"));
+        assert!(wildmatch(b"[]]", b"]"));
+        assert!(wildmatch(b"[a-]", b"-"));
+        assert!(wildmatch(b"[\\]]", b"]"));
+        assert!(wildmatch(b"[ab", b"[ab"));
+        assert!(wildmatch(b"\\*", b"*"));
+        assert!(!wildmatch(b"\\*", b"a"));
+        assert!(wildmatch(b"\\#x", b"#x"));
+    }
+
+    #[test]
+    fn match_class_returns_rest() {
+        assert_eq!(
+            match_class(b"abc]rest", Some(b'b')),
+            Some((true, &b"rest"[..]))
+        );
+        assert_eq!(match_class(b"!abc]", Some(b'b')), Some((false, &b""[..])));
+        assert_eq!(match_class(b"0-9", Some(b'5')), None);
+    }
+
+    #[test]
+    fn addons_directory_patterns() {
+        assert!(ignored("/addons/", "addons/gut", true));
+        assert!(ignored("addons/", "addons/gut/plugin.cfg", false));
+        assert!(!ignored("/addons/", "sub/addons/gut", true));
+        assert!(ignored("addons", "sub/addons", true));
+        assert!(ignored("addons/**", "addons/gut", true));
+        assert!(ignored("/addons/*", "addons/gut", true));
+        assert!(!ignored("/addons/", "addons", false));
+    }
+
+    #[test]
+    fn negation() {
+        assert!(!ignored("/addons/*\n!/addons/gut/", "addons/gut", true));
+        assert!(ignored("/addons/*\n!/addons/gut/", "addons/dialogic", true));
+        // Nothing inside an ignored directory can be re-included
+        assert!(ignored("/addons/\n!/addons/gut/", "addons/gut", true));
+        // The last matching pattern decides
+        assert!(ignored("!/addons/gut/\n/addons/*", "addons/gut", true));
+    }
+
+    #[test]
+    fn parse_comments_and_escapes() {
+        assert!(!ignored("# addons/", "# addons", true));
+        assert!(ignored("\\#notes", "#notes", false));
+        assert!(ignored("\\!keep", "!keep", false));
+        assert!(ignored("logs   ", "logs", false));
+        assert!(ignored("a\\ ", "a ", false));
+        assert!(!ignored("build/", "build", false));
+    }
+
+    #[test]
+    fn render_adds_block() {
+        let config = config_with_gut();
+        let rendered = render("build/\n", &config);
+        assert_eq!(
+            rendered,
+            format!("build/\n\n{}\n/addons/gut/\n{}\n", BLOCK_BEGIN, BLOCK_END)
+        );
+        assert_eq!(render(&rendered, &config), rendered);
+    }
+
+    #[test]
+    fn render_skips_ignored_addons() {
+        let config = config_with_gut();
+        assert_eq!(render("addons/**\n", &config), "addons/**\n");
+    }
+
+    #[test]
+    fn render_keeps_block_position() {
+        let config = config_with_gut();
+        let content = format!("a\n{}\n/addons/old/\n{}\nb\n", BLOCK_BEGIN, BLOCK_END);
+        assert_eq!(
+            render(&content, &config),
+            format!("a\n{}\n/addons/gut/\n{}\nb\n", BLOCK_BEGIN, BLOCK_END)
+        );
+    }
+
+    #[test]
+    fn render_removes_block_and_legacy_entries() {
+        let config = config("version: 1\naddons: []\n");
+        let content = format!("a\n\n{}\n/addons/old/\n{}\n", BLOCK_BEGIN, BLOCK_END);
+        assert_eq!(render(&content, &config), "a\n");
+        let config = config_with_gut();
+        assert_eq!(
+            render("a\naddons/gut\n", &config),
+            format!("a\n\n{}\n/addons/gut/\n{}\n", BLOCK_BEGIN, BLOCK_END)
+        );
+    }
+
+    #[test]
+    fn render_without_block_end() {
+        let config = config_with_gut();
+        let content = format!("{}\n/addons/old/\nmine.txt\n", BLOCK_BEGIN);
+        assert_eq!(
+            render(&content, &config),
+            format!("{}\n/addons/gut/\n{}\nmine.txt\n", BLOCK_BEGIN, BLOCK_END)
+        );
+    }
+
+    #[test]
+    fn render_vendor_mode_keeps_user_rules() {
+        let config = config("version: 1\nvendor: true\naddons: []\n");
+        assert_eq!(
+            render(
+                "addons/\naddons/foo\naddons/foo/build/\naddons/*.tmp\n",
+                &config
+            ),
+            "addons/foo/build/\naddons/*.tmp\n"
+        );
+    }
+
+    #[test]
+    fn render_vendor_mode() {
+        let config = config(
+            "version: 1\nvendor: true\naddons:\n- {name: gut, version: '', source: s, checksum: c}\n",
+        );
+        let content = format!(
+            "a\naddons/\n\n{}\n/addons/gut/\n{}\n",
+            BLOCK_BEGIN, BLOCK_END
+        );
+        assert_eq!(render(&content, &config), "a\n");
+    }
+}
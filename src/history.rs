@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Addon;
+use crate::error::{Error, Result};
+use crate::project::Project;
+
+/// Previous states of addons changed by install, upgrade and uninstall
+pub const HISTORY_FILE: &str = ".gdam/history.yaml";
+
+/// Number of operations kept, older ones are dropped
+const MAX_OPERATIONS: usize = 50;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(default)]
+    pub operations: Vec<Operation>,
+}
+
+/// A command run that changed addons in gdam.yaml
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Operation {
+    /// Command that made the changes, e.g. `install`, `upgrade` or `uninstall`
+    pub command: String,
+    /// Unix timestamp
+    pub time: i64,
+    pub changes: Vec<Change>,
+}
+
+/// The state of an addon before an operation changed it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Change {
+    pub name: String,
+    /// Entry in gdam.yaml before the change, None if the addon was newly installed
+    #[serde(default)]
+    pub previous: Option<Addon>,
+}
+
+impl History {
+    /// Load the history, empty if there is none yet
+    pub fn load(project: &Project) -> Result<Self> {
+        let path = project.path(HISTORY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        serde_yaml::from_str(&content).map_err(|e| Error::yaml(HISTORY_FILE, e))
+    }
+
+    pub fn save(&self, project: &Project) -> Result<()> {
+        let path = project.path(HISTORY_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_yaml::to_string(self)
+            .map_err(|e| Error::Other(format!("Failed to serialize history: {}", e)))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Record the previous state of the addons changed by a command
+    pub fn record(project: &Project, command: &str, changes: Vec<Change>) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut history = Self::load(project)?;
+        history.operations.push(Operation {
+            command: command.to_string(),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or_default(),
+            changes,
+        });
+        let excess = history.operations.len().saturating_sub(MAX_OPERATIONS);
+        history.operations.drain(..excess);
+        history.save(project)
+    }
+
+    /// Remove the changes matching `filter` from the last operation that has any,
+    /// dropping the operation once all its changes are taken. Returns them with
+    /// the command and time of their operation, so rolling back again goes one
+    /// step further back instead of undoing the rollback.
+    pub fn take(&mut self, filter: impl Fn(&Change) -> bool) -> Option<Operation> {
+        let index = self
+            .operations
+            .iter()
+            .rposition(|op| op.changes.iter().any(&filter))?;
+        let operation = &mut self.operations[index];
+        let (taken, kept) = operation.changes.drain(..).partition(&filter);
+        operation.changes = kept;
+        let taken = Operation {
+            command: operation.command.clone(),
+            time: operation.time,
+            changes: taken,
+        };
+        if self.operations[index].changes.is_empty() {
+            self.operations.remove(index);
+        }
+        Some(taken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(command: &str, names: &[&str]) -> Operation {
+        Operation {
+            command: command.to_string(),
+            time: 0,
+            changes: names
+                .iter()
+                .map(|name| Change {
+                    name: name.to_string(),
+                    previous: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn names(operation: &Operation) -> Vec<&str> {
+        operation.changes.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn take_twice_goes_further_back() {
+        let mut history = History {
+            operations: vec![
+                operation("install", &["foo"]),
+                operation("upgrade", &["foo", "bar"]),
+            ],
+        };
+
+        let first = history.take(|_| true).unwrap();
+        assert_eq!(first.command, "upgrade");
+        assert_eq!(names(&first), ["foo", "bar"]);
+
+        let second = history.take(|_| true).unwrap();
+        assert_eq!(second.command, "install");
+        assert_eq!(names(&second), ["foo"]);
+
+        assert!(history.take(|_| true).is_none());
+    }
+
+    #[test]
+    fn take_one_addon_keeps_the_others() {
+        let mut history = History {
+            operations: vec![
+                operation("install", &["foo"]),
+                operation("upgrade", &["foo", "bar"]),
+                operation("install", &["baz"]),
+            ],
+        };
+
+        let taken = history.take(|c| c.name == "foo").unwrap();
+        assert_eq!(taken.command, "upgrade");
+        assert_eq!(names(&taken), ["foo"]);
+        assert_eq!(names(&history.operations[1]), ["bar"]);
+
+        let taken = history.take(|c| c.name == "foo").unwrap();
+        assert_eq!(taken.command, "install");
+        assert_eq!(history.operations.len(), 2);
+        assert!(history.take(|c| c.name == "foo").is_none());
+    }
+}
@@ -1,29 +1,196 @@
+// First, so its macros are available to every other module
+#[macro_use]
+pub mod output;
+
+pub mod changelog;
 mod cli;
 mod commands;
 pub mod config;
 mod error;
 pub mod git;
+pub mod gitignore;
+pub mod history;
+pub mod package;
 pub mod plugin;
+pub mod project;
+pub mod registry;
+pub mod settings;
 pub mod utils;
+pub mod version;
+pub mod workspace;
+
+use std::path::PathBuf;
 
 use clap::Parser;
 use cli::{Cli, Commands};
+use colored::Colorize;
+use error::Result;
+use output::{Format, report_changes, report_workspace_changes};
+use project::{Location, Project};
 
 fn main() {
     let cli = Cli::parse();
+    output::init(if cli.json { Format::Json } else { cli.format });
+
+    match run(cli) {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            if output::is_json() {
+                output::print_error(&e);
+            } else {
+                eprintln!("Error: {}", e);
+                if let Some(hint) = e.hint() {
+                    eprintln!("{} {}", "Hint:".cyan(), hint);
+                }
+            }
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+/// Run a command, returns the exit code of a successful run
+fn run(cli: Cli) -> Result<i32> {
+    // `gdam config` still runs with broken settings, to fix them
+    match settings::init() {
+        Err(e) if matches!(cli.command, Commands::Config { .. }) => {
+            eprintln!("Warning: {}", e);
+        }
+        result => result?,
+    }
+
+    // Commands look for their project from here up to the filesystem root
+    let start = cli.project.unwrap_or_else(|| PathBuf::from("."));
+    let locate = || Location::discover(&start);
+    let project = || -> Result<Project> { locate()?.into_project() };
 
     let result = match cli.command {
         Commands::Version => commands::version::execute(),
-        Commands::Init => commands::init::execute(),
-        Commands::Install { git_url } => commands::install::execute(git_url),
-        Commands::List => commands::list::execute(),
-        Commands::Uninstall { name } => commands::uninstall::execute(name),
-        Commands::Upgrade { addon_name } => commands::upgrade::execute(addon_name),
+        Commands::Init {
+            yes,
+            vendor,
+            registries,
+        } => {
+            let project = project()?;
+            report_changes(&project, || {
+                commands::init::execute(&project, yes, vendor, registries)
+            })
+        }
+        Commands::Install {
+            git_url,
+            git_ref,
+            run_hooks,
+            force,
+            alias,
+            policy,
+        } => {
+            let options = commands::install::InstallOptions {
+                git_ref,
+                run_hooks,
+                force,
+                alias,
+                policy,
+            };
+            match locate()? {
+                Location::Project(project) => report_changes(&project, || {
+                    commands::install::execute(&project, git_url, options)
+                }),
+                Location::Workspace(root) => report_workspace_changes(&root, || {
+                    commands::workspace::install(&root, git_url, options)
+                }),
+            }
+        }
+        Commands::Adopt {
+            addon_name,
+            git_url,
+        } => {
+            let project = project()?;
+            report_changes(&project, || {
+                commands::adopt::execute(&project, addon_name, git_url)
+            })
+        }
+        Commands::Import { from, remove_old } => {
+            let project = project()?;
+            report_changes(&project, || {
+                commands::import::execute(&project, from, remove_old)
+            })
+        }
+        Commands::List => commands::list::execute(&project()?),
+        Commands::Search { term } => commands::search::execute(&project()?, term),
+        Commands::Uninstall { name } => {
+            let project = project()?;
+            report_changes(&project, || commands::uninstall::execute(&project, name))
+        }
+        Commands::Outdated => {
+            let outdated = commands::outdated::execute(&project()?)?;
+            return Ok(if outdated {
+                commands::outdated::EXIT_UPDATES_AVAILABLE
+            } else {
+                0
+            });
+        }
+        Commands::Upgrade {
+            addon_name,
+            dry_run,
+            confirm,
+            to,
+        } => {
+            let options = commands::upgrade::UpgradeOptions {
+                dry_run,
+                confirm,
+                to,
+            };
+            // A dry run reports what would change instead of what did
+            match locate()? {
+                Location::Project(project) if dry_run && output::is_json() => {
+                    let preview = commands::upgrade::execute(&project, addon_name, options)?;
+                    output::print_json(&preview)
+                }
+                Location::Project(project) => report_changes(&project, || {
+                    commands::upgrade::execute(&project, addon_name, options).map(|_| ())
+                }),
+                Location::Workspace(root) if dry_run && output::is_json() => {
+                    let preview = commands::workspace::upgrade(&root, addon_name, options)?;
+                    output::print_json(&preview)
+                }
+                Location::Workspace(root) => report_workspace_changes(&root, || {
+                    commands::workspace::upgrade(&root, addon_name, options).map(|_| ())
+                }),
+            }
+        }
+        Commands::Verify => {
+            let verified = commands::verify::execute(&project()?)?;
+            return Ok(if verified {
+                0
+            } else {
+                output::EXIT_CHECK_FAILED
+            });
+        }
+        Commands::Gitignore { check } => {
+            let up_to_date = commands::gitignore::execute(&project()?, check)?;
+            return Ok(if check && !up_to_date {
+                output::EXIT_CHECK_FAILED
+            } else {
+                0
+            });
+        }
+        Commands::Rollback { addon_name } => {
+            let project = project()?;
+            report_changes(&project, || {
+                commands::rollback::execute(&project, addon_name)
+            })
+        }
+        Commands::Changelog {
+            addon_name,
+            from,
+            to,
+        } => commands::changelog::execute(&project()?, addon_name, from, to),
         Commands::Cache { action } => commands::cache::execute(action),
+        Commands::Config { action } => commands::config::execute(action),
+        Commands::Package { action } => {
+            let valid = commands::package::execute(action, &start)?;
+            return Ok(if valid { 0 } else { output::EXIT_CHECK_FAILED });
+        }
     };
-
-    if let Err(e) = result {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
-    }
+    result.map(|()| 0)
 }
@@ -0,0 +1,307 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::config::{Addon, Config, UpgradePolicy};
+use crate::error::{Error, Result};
+use crate::project::Project;
+use crate::workspace::Workspace;
+
+/// How commands print their results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Format {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// A single JSON document on stdout
+    Json,
+}
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+/// Exit code of `verify` and `gitignore --check` when the files don't match
+/// gdam.yaml, and of `package validate` when the manifest has errors. Error
+/// codes stay below 20, see the Exit Codes page of the docs.
+pub const EXIT_CHECK_FAILED: i32 = 21;
+
+/// Print human-readable output. With `--format json` it goes to stderr, so stdout
+/// only holds the JSON result.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Set the format used by the rest of the run
+pub fn init(format: Format) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get() == Some(&Format::Json)
+}
+
+/// Where child processes like hooks should write their output
+pub fn child_stdout() -> Stdio {
+    if is_json() {
+        std::io::stderr().into()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// Print the JSON result of a command
+pub fn print_json(value: &impl Serialize) -> Result<()> {
+    let output = serde_json::to_string_pretty(value)
+        .map_err(|e| Error::Other(format!("Failed to serialize output: {}", e)))?;
+    println!("{}", output);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ErrorOutput<'a> {
+    error: ErrorDetails<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorDetails<'a> {
+    kind: &'a str,
+    code: i32,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+    /// Position of a parse error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+}
+
+/// Print an error as JSON on stdout, where the result would have been
+pub fn print_error(error: &Error) {
+    let (line, column) = match error {
+        Error::ConfigParse { line, column, .. } => (*line, *column),
+        _ => (None, None),
+    };
+    let output = ErrorOutput {
+        error: ErrorDetails {
+            kind: error.kind(),
+            code: error.exit_code(),
+            message: error.to_string(),
+            hint: error.hint(),
+            line,
+            column,
+        },
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&output) {
+        println!("{}", json);
+    }
+}
+
+/// An addon as shown in JSON output
+#[derive(Debug, Serialize)]
+pub struct AddonInfo {
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    pub commit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<UpgradePolicy>,
+    pub transitive: bool,
+}
+
+impl From<&Addon> for AddonInfo {
+    fn from(addon: &Addon) -> Self {
+        AddonInfo {
+            name: addon.name.clone(),
+            version: addon.version.clone(),
+            source: addon.source.clone(),
+            commit: addon.checksum.clone(),
+            track: addon.track.clone(),
+            policy: addon.policy,
+            transitive: addon.transitive,
+        }
+    }
+}
+
+/// An addon moved to another commit
+#[derive(Debug, Serialize)]
+pub struct Upgraded {
+    pub name: String,
+    pub source: String,
+    pub from_version: String,
+    pub to_version: String,
+    pub from_commit: String,
+    pub to_commit: String,
+}
+
+/// What a command changed in gdam.yaml
+#[derive(Debug, Default, Serialize)]
+pub struct Changes {
+    pub installed: Vec<AddonInfo>,
+    pub upgraded: Vec<Upgraded>,
+    pub removed: Vec<AddonInfo>,
+}
+
+impl Changes {
+    /// Compare the addons of gdam.yaml before and after a command
+    pub fn between(before: &[Addon], after: &[Addon]) -> Self {
+        let mut changes = Changes::default();
+        for addon in after {
+            match before.iter().find(|a| a.name == addon.name) {
+                None => changes.installed.push(addon.into()),
+                Some(old) if old.source != addon.source => {
+                    changes.removed.push(old.into());
+                    changes.installed.push(addon.into());
+                }
+                Some(old) if old.checksum != addon.checksum => changes.upgraded.push(Upgraded {
+                    name: addon.name.clone(),
+                    source: addon.source.clone(),
+                    from_version: old.version.clone(),
+                    to_version: addon.version.clone(),
+                    from_commit: old.checksum.clone(),
+                    to_commit: addon.checksum.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for addon in before {
+            if !after.iter().any(|a| a.name == addon.name) {
+                changes.removed.push(addon.into());
+            }
+        }
+        changes
+    }
+}
+
+/// Addons of a project, none if it has no gdam.yaml yet
+fn addons_of(project: &Project) -> Result<Vec<Addon>> {
+    if Config::exists(project) {
+        Ok(Config::load(project)?.addons)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// Run a command changing the addons of a project, then print its changes as JSON
+pub fn report_changes(project: &Project, run: impl FnOnce() -> Result<()>) -> Result<()> {
+    if !is_json() {
+        return run();
+    }
+    let before = addons_of(project)?;
+    run()?;
+    print_json(&Changes::between(&before, &addons_of(project)?))
+}
+
+#[derive(Serialize)]
+struct MemberChanges {
+    member: String,
+    #[serde(flatten)]
+    changes: Changes,
+}
+
+#[derive(Serialize)]
+struct WorkspaceChanges {
+    members: Vec<MemberChanges>,
+}
+
+/// Run a command across the members of a workspace, then print the changes of
+/// each member as JSON
+pub fn report_workspace_changes(root: &Path, run: impl FnOnce() -> Result<()>) -> Result<()> {
+    if !is_json() {
+        return run();
+    }
+    let members = Workspace::load(root)?.member_dirs(root)?;
+    let mut before = Vec::new();
+    for dir in &members {
+        before.push(addons_of(&Project::at(root.join(dir)))?);
+    }
+    run()?;
+
+    let mut output = WorkspaceChanges {
+        members: Vec::new(),
+    };
+    for (dir, before) in members.iter().zip(before) {
+        let after = addons_of(&Project::at(root.join(dir)))?;
+        output.members.push(MemberChanges {
+            member: dir.display().to_string(),
+            changes: Changes::between(&before, &after),
+        });
+    }
+    print_json(&output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addon(name: &str, source: &str, checksum: &str) -> Addon {
+        Addon {
+            name: name.to_string(),
+            version: format!("{}.0", checksum),
+            source: source.to_string(),
+            checksum: checksum.to_string(),
+            track: None,
+            transitive: false,
+            dependencies: Vec::new(),
+            upstream_name: None,
+            policy: None,
+        }
+    }
+
+    fn names(addons: &[AddonInfo]) -> Vec<&str> {
+        addons.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    #[test]
+    fn changes_between() {
+        let before = [
+            addon("kept", "https://a", "1"),
+            addon("moved", "https://a", "1"),
+            addon("replaced", "https://b", "1"),
+            addon("dropped", "https://c", "1"),
+        ];
+        let after = [
+            addon("kept", "https://a", "1"),
+            addon("moved", "https://a", "2"),
+            addon("replaced", "https://d", "1"),
+            addon("new", "https://e", "1"),
+        ];
+        let changes = Changes::between(&before, &after);
+
+        assert_eq!(names(&changes.installed), ["replaced", "new"]);
+        assert_eq!(changes.installed[0].source, "https://d");
+        assert_eq!(names(&changes.removed), ["replaced", "dropped"]);
+        assert_eq!(changes.removed[0].source, "https://b");
+
+        let [upgraded] = changes.upgraded.as_slice() else {
+            panic!("{:?}", changes.upgraded);
+        };
+        assert_eq!(upgraded.name, "moved");
+        assert_eq!(
+            (upgraded.from_version.as_str(), upgraded.to_version.as_str()),
+            ("1.0", "2.0")
+        );
+        assert_eq!(
+            (upgraded.from_commit.as_str(), upgraded.to_commit.as_str()),
+            ("1", "2")
+        );
+    }
+
+    #[test]
+    fn no_changes() {
+        let addons = [addon("kept", "https://a", "1")];
+        let changes = Changes::between(&addons, &addons);
+        assert!(changes.installed.is_empty());
+        assert!(changes.upgraded.is_empty());
+        assert!(changes.removed.is_empty());
+    }
+}
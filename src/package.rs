@@ -0,0 +1,460 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::ADDONS_DIR;
+use crate::error::{Error, Result};
+use crate::git::{hash_files, list_dirs_at, read_file_at};
+use crate::plugin::parse_plugin_cfg_str;
+use crate::utils::{copy_dir_excluding, is_excluded};
+
+/// Extensions of files Godot generates next to addon files
+const GENERATED_EXTENSIONS: &[&str] = &["import", "uid"];
+
+/// Manifest shipped by addon repositories to describe themselves
+pub const PACKAGE_FILE: &str = "gdam-package.yaml";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PackageManifest {
+    /// Package name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Package version, used when plugin.cfg has none
+    #[serde(default)]
+    pub version: Option<String>,
+    /// SPDX license identifier
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Supported Godot versions, e.g. `>=4.2, <5`
+    #[serde(default)]
+    pub godot: Option<String>,
+    /// Addon directories provided, relative to the repository root.
+    /// Every folder in `addons/` if empty.
+    #[serde(default)]
+    pub addons: Vec<String>,
+    /// Patterns of files not copied into projects, relative to each addon directory
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    /// Autoload singletons registered in project.godot on install
+    #[serde(default)]
+    pub autoloads: Vec<Autoload>,
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+
+/// Another addon repository a package depends on
+#[derive(Debug, Clone, Deserialize)]
+pub struct Dependency {
+    pub source: String,
+    /// Branch, tag or commit required (any if not set)
+    #[serde(default, rename = "ref")]
+    pub git_ref: Option<String>,
+}
+
+/// An autoload singleton, e.g. `name: Dialogic`, `path: res://addons/dialogic/handler.gd`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Autoload {
+    pub name: String,
+    pub path: String,
+}
+
+/// Commands run in the project directory, only when the user allows it
+#[derive(Debug, Default, Deserialize)]
+pub struct Hooks {
+    #[serde(default)]
+    pub post_install: Vec<String>,
+}
+
+/// An addon directory provided by a repository
+#[derive(Debug)]
+pub struct ProvidedAddon {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl PackageManifest {
+    /// Load the package manifest from a repository, returns None if it has none
+    pub fn load(repo_dir: &Path) -> Result<Option<Self>> {
+        let path = repo_dir.join(PACKAGE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        serde_yaml::from_str(&content)
+            .map(Some)
+            .map_err(|e| Error::yaml(PACKAGE_FILE, e))
+    }
+}
+
+/// List the addons a repository provides: the directories declared in its
+/// package manifest, or every folder in `addons/` otherwise. A repository
+/// without `addons/` but with a plugin.cfg at its root is a single addon
+/// named after the repository.
+pub fn provided_addons(
+    repo_dir: &Path,
+    manifest: Option<&PackageManifest>,
+) -> Result<Vec<ProvidedAddon>> {
+    if let Some(manifest) = manifest.filter(|m| !m.addons.is_empty()) {
+        let mut addons = Vec::new();
+        for dir in &manifest.addons {
+            let name = addon_dir_name(dir)?;
+            let path = repo_dir.join(dir.trim_end_matches('/'));
+            if !path.is_dir() {
+                return Err(Error::AddonMissingInRepo(format!(
+                    "Directory '{}' declared in {} does not exist",
+                    dir, PACKAGE_FILE
+                )));
+            }
+            // Symbolic links must not lead out of the repository either
+            if !path.canonicalize()?.starts_with(repo_dir.canonicalize()?) {
+                return Err(invalid_addon_dir(dir));
+            }
+            addons.push(ProvidedAddon { name, path });
+        }
+        return Ok(addons);
+    }
+
+    let addons_dir = repo_dir.join(ADDONS_DIR);
+    if !addons_dir.exists() {
+        if repo_dir.join("plugin.cfg").is_file() {
+            return Ok(vec![ProvidedAddon {
+                name: root_addon_name(repo_dir),
+                path: repo_dir.to_path_buf(),
+            }]);
+        }
+        return Ok(Vec::new());
+    }
+
+    let mut addons = Vec::new();
+    for entry in fs::read_dir(&addons_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            addons.push(ProvidedAddon {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: entry.path(),
+            });
+        }
+    }
+    Ok(addons)
+}
+
+/// Get the addon name of a directory declared in a package manifest. The
+/// directory must be a relative path staying inside the repository.
+fn addon_dir_name(dir: &str) -> Result<String> {
+    let path = Path::new(dir.trim_end_matches('/'));
+    let inside_repo = path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    match path.file_name() {
+        Some(name) if inside_repo => Ok(name.to_string_lossy().to_string()),
+        _ => Err(invalid_addon_dir(dir)),
+    }
+}
+
+/// Name of the addon a repository with a plugin.cfg at its root provides
+fn root_addon_name(repo_dir: &Path) -> String {
+    repo_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Whether a repository is a single addon at its root at a given revision
+fn is_root_addon_at(repo_dir: &Path, rev: &str) -> bool {
+    read_file_at(repo_dir, rev, "plugin.cfg").is_some()
+        && list_dirs_at(repo_dir, rev, ADDONS_DIR).is_ok_and(|dirs| dirs.is_empty())
+}
+
+fn invalid_addon_dir(dir: &str) -> Error {
+    Error::Other(format!(
+        "Invalid addon directory '{}' in {}: it must be a relative path inside the repository",
+        dir, PACKAGE_FILE
+    ))
+}
+
+/// Find an addon provided by a repository by name
+pub fn find_provided_addon(repo_dir: &Path, name: &str) -> Result<Option<ProvidedAddon>> {
+    let manifest = PackageManifest::load(repo_dir)?;
+    Ok(provided_addons(repo_dir, manifest.as_ref())?
+        .into_iter()
+        .find(|a| a.name == name))
+}
+
+/// Read the package manifest of a repository at a given revision, None if it
+/// has none or it can't be parsed
+pub fn manifest_at(repo_dir: &Path, rev: &str) -> Option<PackageManifest> {
+    read_file_at(repo_dir, rev, PACKAGE_FILE)
+        .and_then(|content| serde_yaml::from_str(&content).ok())
+}
+
+/// Get the path of an addon in a repository at a given revision, relative to
+/// the repository root, without touching the working tree
+pub fn provided_addon_path_at(repo_dir: &Path, rev: &str, name: &str) -> String {
+    manifest_at(repo_dir, rev)
+        .and_then(|m| {
+            m.addons
+                .into_iter()
+                .find(|dir| addon_dir_name(dir).is_ok_and(|n| n == name))
+        })
+        .map(|dir| dir.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| {
+            if name == root_addon_name(repo_dir) && is_root_addon_at(repo_dir, rev) {
+                ".".to_string()
+            } else {
+                format!("{}/{}", ADDONS_DIR, name)
+            }
+        })
+}
+
+/// List the names of the addons a repository provides at a given revision,
+/// without touching the working tree
+pub fn provided_addon_names_at(repo_dir: &Path, rev: &str) -> Result<Vec<String>> {
+    match manifest_at(repo_dir, rev).filter(|m| !m.addons.is_empty()) {
+        Some(manifest) => manifest
+            .addons
+            .iter()
+            .map(|dir| addon_dir_name(dir))
+            .collect(),
+        None if is_root_addon_at(repo_dir, rev) => Ok(vec![root_addon_name(repo_dir)]),
+        None => list_dirs_at(repo_dir, rev, ADDONS_DIR),
+    }
+}
+
+/// Read the plugin.cfg version of an addon at a given revision
+pub fn addon_version_at(repo_dir: &Path, rev: &str, name: &str) -> Option<String> {
+    let path = format!("{}/plugin.cfg", provided_addon_path_at(repo_dir, rev, name));
+    let content = read_file_at(repo_dir, rev, &path)?;
+    parse_plugin_cfg_str(&content).version
+}
+
+/// Copy an addon into the project, replacing any existing copy and
+/// skipping files excluded by the package manifest
+pub fn copy_addon(src: &Path, dst: &Path, manifest: Option<&PackageManifest>) -> Result<()> {
+    if dst.exists() {
+        fs::remove_dir_all(dst)?;
+    }
+    let exclude = manifest.map(|m| m.exclude.as_slice()).unwrap_or_default();
+    copy_dir_excluding(src, dst, exclude)
+}
+
+/// A file of an addon directory on disk
+#[derive(Debug)]
+pub struct LocalFile {
+    /// Path relative to the addon directory
+    pub path: String,
+    /// Blob hash of the file as is
+    pub hash: String,
+    /// Blob hash with CRLF line endings converted to LF, matching text files
+    /// git checked out with CRLF line endings
+    pub normalized_hash: String,
+}
+
+/// Compute the git blob hashes of the files of an addon directory
+pub fn hash_addon_files(dir: &Path) -> Result<Vec<LocalFile>> {
+    let mut files = Vec::new();
+    list_local_files(dir, "", &mut files)?;
+    let paths: Vec<PathBuf> = files.iter().map(|f| dir.join(f)).collect();
+    let hashes = hash_files(&paths, false)?;
+    let normalized = hash_files(&paths, true)?;
+    Ok(files
+        .into_iter()
+        .zip(hashes.into_iter().zip(normalized))
+        .map(|(path, (hash, normalized_hash))| LocalFile {
+            path,
+            hash,
+            normalized_hash,
+        })
+        .collect())
+}
+
+/// List the files of a directory recursively, relative to it with `/` separators,
+/// skipping nested `.git` directories
+fn list_local_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" {
+            continue;
+        }
+        let rel_path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if entry.file_type()?.is_dir() {
+            list_local_files(&entry.path(), &rel_path, files)?;
+        } else {
+            files.push(rel_path);
+        }
+    }
+    Ok(())
+}
+
+/// Compare local files with the files of a commit, as (status, path) pairs
+/// where status is `A` (only on disk), `D` (missing on disk) or `M` (modified)
+pub fn compare_addon_files(
+    local: &[LocalFile],
+    upstream: &[(String, String)],
+    exclude: &[String],
+) -> Vec<(char, String)> {
+    let mut diff = Vec::new();
+
+    for (hash, file) in upstream {
+        // Excluded files and files in excluded directories are never installed
+        let excluded = is_excluded(exclude, file, false)
+            || file
+                .match_indices('/')
+                .any(|(i, _)| is_excluded(exclude, &file[..i], true));
+        if excluded {
+            continue;
+        }
+        match local.iter().find(|f| f.path == *file) {
+            Some(f) if f.hash == *hash || f.normalized_hash == *hash => {}
+            Some(_) => diff.push(('M', file.clone())),
+            None => diff.push(('D', file.clone())),
+        }
+    }
+
+    for LocalFile { path, .. } in local {
+        let generated = Path::new(path)
+            .extension()
+            .is_some_and(|ext| GENERATED_EXTENSIONS.iter().any(|g| ext == *g));
+        if !generated && !upstream.iter().any(|(_, f)| f == path) {
+            diff.push(('A', path.clone()));
+        }
+    }
+
+    diff.sort_by(|a, b| a.1.cmp(&b.1));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(hash, file)| (hash.to_string(), file.to_string()))
+            .collect()
+    }
+
+    fn compare(
+        local: &[(&str, &str)],
+        upstream: &[(&str, &str)],
+        exclude: &[&str],
+    ) -> Vec<(char, String)> {
+        let exclude: Vec<String> = exclude.iter().map(|p| p.to_string()).collect();
+        let local: Vec<LocalFile> = local
+            .iter()
+            .map(|(hash, path)| LocalFile {
+                path: path.to_string(),
+                hash: hash.to_string(),
+                normalized_hash: hash.to_string(),
+            })
+            .collect();
+        compare_addon_files(&local, &files(upstream), &exclude)
+    }
+
+    fn diff(entries: &[(char, &str)]) -> Vec<(char, String)> {
+        entries.iter().map(|(s, f)| (*s, f.to_string())).collect()
+    }
+
+    #[test]
+    fn identical_files() {
+        let same = [("1", "plugin.cfg"), ("2", "src/main.gd")];
+        assert!(compare(&same, &same, &[]).is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_modified_files() {
+        assert_eq!(
+            compare(
+                &[("1", "plugin.cfg"), ("9", "src/main.gd"), ("3", "extra.gd")],
+                &[
+                    ("1", "plugin.cfg"),
+                    ("2", "src/main.gd"),
+                    ("4", "docs/a.md")
+                ],
+                &[],
+            ),
+            diff(&[('D', "docs/a.md"), ('A', "extra.gd"), ('M', "src/main.gd")])
+        );
+    }
+
+    #[test]
+    fn generated_files_are_ignored() {
+        assert!(
+            compare(
+                &[
+                    ("1", "icon.png"),
+                    ("2", "icon.png.import"),
+                    ("3", "main.gd.uid")
+                ],
+                &[("1", "icon.png")],
+                &[],
+            )
+            .is_empty()
+        );
+        // Unless they are part of the addon
+        assert_eq!(
+            compare(&[("1", "main.gd.uid")], &[("2", "main.gd.uid")], &[]),
+            diff(&[('M', "main.gd.uid")])
+        );
+    }
+
+    #[test]
+    fn excluded_files_are_not_missing() {
+        assert!(
+            compare(
+                &[("1", "plugin.cfg")],
+                &[
+                    ("1", "plugin.cfg"),
+                    ("2", "tests/a.gd"),
+                    ("3", "docs/x/y.md"),
+                    ("4", "a.md")
+                ],
+                &["tests/", "docs", "*.md"],
+            )
+            .is_empty()
+        );
+        // Directory patterns don't exclude files of the same name
+        assert_eq!(
+            compare(&[], &[("1", "tests")], &["tests/"]),
+            diff(&[('D', "tests")])
+        );
+    }
+
+    #[test]
+    fn crlf_checkouts_match_lf_blobs() {
+        let dir = std::env::temp_dir().join(format!("gdam-test-crlf-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.gd"), "a\r\nb\r\n").unwrap();
+        fs::write(dir.join("icon.bin"), b"\0\r\n").unwrap();
+        let local = hash_addon_files(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let local = local.unwrap();
+
+        // `git hash-object` of "a\nb\n", the blob of the LF original
+        let lf_blob = "422c2b7ab3b3c668038da977e4e93a5fc623169c";
+        let binary = local.iter().find(|f| f.path == "icon.bin").unwrap();
+        assert_eq!(binary.hash, binary.normalized_hash);
+        let upstream = vec![
+            (lf_blob.to_string(), "main.gd".to_string()),
+            (binary.hash.clone(), "icon.bin".to_string()),
+        ];
+        assert!(compare_addon_files(&local, &upstream, &[]).is_empty());
+
+        // Files committed with CRLF still match as is
+        let text = local.iter().find(|f| f.path == "main.gd").unwrap();
+        assert_ne!(text.hash, lf_blob);
+        let upstream = vec![
+            (text.hash.clone(), "main.gd".to_string()),
+            (binary.hash.clone(), "icon.bin".to_string()),
+        ];
+        assert!(compare_addon_files(&local, &upstream, &[]).is_empty());
+    }
+}
@@ -10,12 +10,15 @@ pub struct PluginInfo {
 
 /// Parse a Godot plugin.cfg file (INI format)
 pub fn parse_plugin_cfg(path: &Path) -> PluginInfo {
-    let mut info = PluginInfo::default();
+    match fs::read_to_string(path) {
+        Ok(content) => parse_plugin_cfg_str(&content),
+        Err(_) => PluginInfo::default(),
+    }
+}
 
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => return info,
-    };
+/// Parse the contents of a Godot plugin.cfg file
+pub fn parse_plugin_cfg_str(content: &str) -> PluginInfo {
+    let mut info = PluginInfo::default();
 
     let mut in_plugin_section = false;
 
@@ -27,13 +30,11 @@ pub fn parse_plugin_cfg(path: &Path) -> PluginInfo {
             continue;
         }
 
-        if in_plugin_section {
-            if let Some((key, value)) = parse_ini_line(line) {
-                match key {
-                    "name" => info.name = Some(value),
-                    "version" => info.version = Some(value),
-                    _ => {}
-                }
+        if in_plugin_section && let Some((key, value)) = parse_ini_line(line) {
+            match key {
+                "name" => info.name = Some(value),
+                "version" => info.version = Some(value),
+                _ => {}
             }
         }
     }
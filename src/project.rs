@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::{ADDONS_DIR, CONFIG_FILE};
+use crate::error::{Error, Result};
+use crate::utils::is_godot_project;
+use crate::workspace::WORKSPACE_FILE;
+
+pub const GODOT_PROJECT_FILE: &str = "project.godot";
+
+/// The Godot project a command works on. Every project file is resolved from its root.
+#[derive(Debug, Clone)]
+pub struct Project {
+    root: PathBuf,
+}
+
+impl Project {
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Project { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve a path relative to the project root
+    pub fn path(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.path(CONFIG_FILE)
+    }
+
+    pub fn addons_dir(&self) -> PathBuf {
+        self.path(ADDONS_DIR)
+    }
+
+    pub fn godot_file(&self) -> PathBuf {
+        self.path(GODOT_PROJECT_FILE)
+    }
+}
+
+/// Where gdam was started, found by looking up from a directory
+pub enum Location {
+    Project(Project),
+    /// Root of a workspace, outside of its member projects
+    Workspace(PathBuf),
+}
+
+impl Location {
+    /// Find the nearest directory, from `start` up to the filesystem root, holding
+    /// a project.godot or a workspace manifest
+    pub fn discover(start: &Path) -> Result<Self> {
+        if !start.is_dir() {
+            return Err(Error::Other(format!(
+                "Directory not found: {}",
+                start.display()
+            )));
+        }
+        let start = std::path::absolute(start)?;
+        for dir in start.ancestors() {
+            if is_godot_project(dir) {
+                return Ok(Location::Project(Project::at(dir)));
+            }
+            if dir.join(WORKSPACE_FILE).exists() {
+                return Ok(Location::Workspace(dir.to_path_buf()));
+            }
+        }
+        Err(Error::NotInProject)
+    }
+
+    /// The project, for commands that don't run across a workspace
+    pub fn into_project(self) -> Result<Project> {
+        match self {
+            Location::Project(project) => Ok(project),
+            Location::Workspace(root) => Err(Error::Other(format!(
+                "{} is a workspace root, run this command in a member project. Only 'gdam install' and 'gdam upgrade' run across the members of {}",
+                root.display(),
+                WORKSPACE_FILE
+            ))),
+        }
+    }
+}
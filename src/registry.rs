@@ -0,0 +1,110 @@
+use serde::Deserialize;
+use std::fs;
+
+use crate::error::{Error, Result};
+use crate::git::{ensure_repo, is_valid_git_url, latest_rev, read_file_at, repo_cache_dir};
+use crate::project::Project;
+use crate::utils::get_cache_dir;
+
+/// Index file names looked up at the root of registry repositories
+const INDEX_FILES: &[&str] = &["index.yaml", "index.yml", "index.json"];
+
+/// Cache subdirectory registry repositories are cloned into
+const REGISTRY_CACHE_DIR: &str = "registries";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RegistryIndex {
+    #[serde(default)]
+    pub addons: Vec<RegistryEntry>,
+}
+
+/// An addon listed in a registry index
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryEntry {
+    /// Short name used with `gdam install <name>`
+    pub name: String,
+    /// Git repository URL
+    pub source: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Supported Godot versions, e.g. `>=4.2, <5`
+    #[serde(default)]
+    pub godot: Option<String>,
+}
+
+impl RegistryEntry {
+    /// Check if the name, description or tags contain a search term (case insensitive)
+    pub fn matches(&self, term: &str) -> bool {
+        let term = term.to_lowercase();
+        self.name.to_lowercase().contains(&term)
+            || self
+                .description
+                .as_ref()
+                .is_some_and(|d| d.to_lowercase().contains(&term))
+            || self.tags.iter().any(|t| t.to_lowercase().contains(&term))
+    }
+}
+
+/// Load a registry index from a local YAML/JSON file, relative to the project
+/// root, or a git repository
+pub fn load_index(project: &Project, location: &str) -> Result<RegistryIndex> {
+    let path = project.path(location);
+    let (content, file) = if path.is_file() {
+        (fs::read_to_string(path)?, location.to_string())
+    } else if is_valid_git_url(location) {
+        let clone_dir = repo_cache_dir(&get_cache_dir()?.join(REGISTRY_CACHE_DIR), location)?;
+        ensure_repo(project, location, &clone_dir)?;
+
+        let rev = latest_rev(&clone_dir, None)?;
+        INDEX_FILES
+            .iter()
+            .find_map(|file| {
+                read_file_at(&clone_dir, &rev, file).map(|c| (c, format!("{}/{}", location, file)))
+            })
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Registry {} has no {}",
+                    location,
+                    INDEX_FILES.join(" or ")
+                ))
+            })?
+    } else {
+        return Err(Error::Other(format!(
+            "Registry '{}' is neither a file nor a git URL",
+            location
+        )));
+    };
+
+    if file.ends_with(".json") {
+        serde_json::from_str(&content).map_err(|e| Error::json(format!("registry {}", file), e))
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| Error::yaml(format!("registry {}", file), e))
+    }
+}
+
+/// Load the entries of several registries. Earlier registries take precedence
+/// when the same name is listed more than once.
+pub fn load_entries(project: &Project, locations: &[String]) -> Result<Vec<RegistryEntry>> {
+    let mut entries: Vec<RegistryEntry> = Vec::new();
+    for location in locations {
+        for entry in load_index(project, location)?.addons {
+            if !entries.iter().any(|e| e.name == entry.name) {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Find an addon by its short name
+pub fn resolve(
+    project: &Project,
+    locations: &[String],
+    name: &str,
+) -> Result<Option<RegistryEntry>> {
+    Ok(load_entries(project, locations)?
+        .into_iter()
+        .find(|e| e.name.eq_ignore_ascii_case(name)))
+}
@@ -0,0 +1,441 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use colored::Colorize;
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// User configuration file, under the XDG config directory
+const SETTINGS_FILE: &str = "gdam/config.yaml";
+
+/// Repositories fetched at the same time unless configured
+const DEFAULT_JOBS: usize = 4;
+
+/// A user-level setting, read from the environment or the user configuration file
+pub struct Key {
+    pub name: &'static str,
+    /// Environment variable overriding the file
+    pub env: &'static str,
+    pub description: &'static str,
+    kind: Kind,
+}
+
+/// What values a setting accepts
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    Count,
+    Flag,
+    Choice(&'static [&'static str]),
+}
+
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "cache_dir",
+        env: "GDAM_CACHE_DIR",
+        description: "Directory repositories are cloned into",
+        kind: Kind::Text,
+    },
+    Key {
+        name: "jobs",
+        env: "GDAM_JOBS",
+        description: "Number of repositories fetched at the same time",
+        kind: Kind::Count,
+    },
+    Key {
+        name: "registry",
+        env: "GDAM_REGISTRY",
+        description: "Registry used by projects that don't list any in gdam.yaml",
+        kind: Kind::Text,
+    },
+    Key {
+        name: "color",
+        env: "GDAM_COLOR",
+        description: "Colored output: auto, always or never",
+        kind: Kind::Choice(&["auto", "always", "never"]),
+    },
+    Key {
+        name: "progress",
+        env: "GDAM_PROGRESS",
+        description: "Show git's progress while cloning and fetching: true or false",
+        kind: Kind::Flag,
+    },
+    Key {
+        name: "git",
+        env: "GDAM_GIT",
+        description: "Git executable",
+        kind: Kind::Text,
+    },
+    Key {
+        name: "godot",
+        env: "GDAM_GODOT",
+        description: "Godot executable, asked for the engine version when project.godot doesn't tell it",
+        kind: Kind::Text,
+    },
+];
+
+/// Entries of the user configuration file that hold rules rather than a value
+pub const RULES: &[&str] = &["rewrites", "mirrors", "auth"];
+
+/// Prefix of the environment variables holding the token of a host, e.g. `GDAM_TOKEN_GITHUB_COM`
+const TOKEN_ENV_PREFIX: &str = "GDAM_TOKEN_";
+
+/// User name sent with a token when the host entry doesn't set one
+const DEFAULT_TOKEN_USERNAME: &str = "oauth2";
+
+/// Credentials for the sources of a host, under `auth` in the user configuration file.
+/// Only read from there, so tokens never end up in gdam.yaml.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostAuth {
+    /// Token sent as the HTTPS password
+    token: Option<String>,
+    /// Environment variable holding the token, instead of writing it in the file
+    token_env: Option<String>,
+    /// User name sent with the token
+    username: Option<String>,
+    /// Private key used for SSH sources
+    ssh_key: Option<String>,
+}
+
+impl fmt::Debug for HostAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostAuth")
+            .field("token", &self.token.as_ref().map(|_| "<hidden>"))
+            .field("token_env", &self.token_env)
+            .field("username", &self.username)
+            .field("ssh_key", &self.ssh_key)
+            .finish()
+    }
+}
+
+/// A token and where it was read from
+pub struct Token {
+    pub username: String,
+    pub secret: String,
+    pub origin: Origin,
+}
+
+/// Where the effective value of a setting comes from
+#[derive(Debug, Clone)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Env(var) => write!(f, "environment variable {}", var),
+        }
+    }
+}
+
+/// The effective user settings
+#[derive(Debug, Default)]
+pub struct Settings {
+    values: BTreeMap<&'static str, (String, Origin)>,
+    /// URL rewrite rules, applied after those of the project
+    rewrites: BTreeMap<String, String>,
+    /// Fallback mirrors of sources, tried after those of the project
+    mirrors: BTreeMap<String, Vec<String>>,
+    /// Credentials by host
+    auth: BTreeMap<String, HostAuth>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Load the settings used by the rest of the run
+pub fn init() -> Result<()> {
+    let settings = Settings::load()?;
+    if let Some(color) = settings.get("color") {
+        match color {
+            "always" => colored::control::set_override(true),
+            "never" => colored::control::set_override(false),
+            _ => {}
+        }
+    }
+    let _ = SETTINGS.set(settings);
+    Ok(())
+}
+
+/// The settings loaded by `init`, defaults if they weren't loaded
+pub fn current() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+/// Path of the user configuration file: `$XDG_CONFIG_HOME/gdam/config.yaml`,
+/// or `~/.config/gdam/config.yaml`
+pub fn settings_file() -> Result<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+        .ok_or_else(|| Error::Other("Cannot find the home directory".to_string()))?;
+    Ok(config_home.join(SETTINGS_FILE))
+}
+
+/// Find a setting by name
+pub fn find_key(name: &str) -> Result<&'static Key> {
+    if RULES.contains(&name) {
+        return Err(Error::Other(format!(
+            "{} can't be set from the command line, edit it in {}",
+            name,
+            settings_file()?.display()
+        )));
+    }
+    KEYS.iter().find(|k| k.name == name).ok_or_else(|| {
+        let names: Vec<&str> = KEYS.iter().map(|k| k.name).collect();
+        Error::Other(format!(
+            "Unknown setting '{}', expected one of: {}",
+            name,
+            names.join(", ")
+        ))
+    })
+}
+
+impl Key {
+    /// Check a value and convert it to the type stored in the file
+    pub fn parse(&self, value: &str) -> Result<serde_yaml::Value> {
+        let invalid = |expected: &str| {
+            Error::Other(format!(
+                "Invalid value '{}' for {}, expected {}",
+                value, self.name, expected
+            ))
+        };
+        match self.kind {
+            Kind::Text if value.is_empty() => Err(invalid("a non-empty value")),
+            Kind::Text => Ok(value.into()),
+            Kind::Count => match value.parse::<u64>() {
+                Ok(n) if n > 0 => Ok(n.into()),
+                _ => Err(invalid("a positive number")),
+            },
+            Kind::Flag => match value {
+                "true" => Ok(true.into()),
+                "false" => Ok(false.into()),
+                _ => Err(invalid("true or false")),
+            },
+            Kind::Choice(choices) if choices.contains(&value) => Ok(value.into()),
+            Kind::Choice(choices) => Err(invalid(&choices.join(", "))),
+        }
+    }
+}
+
+/// Read the user configuration file, empty if there is none
+pub fn read_file() -> Result<BTreeMap<String, serde_yaml::Value>> {
+    let path = settings_file()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    serde_yaml::from_str(&content).map_err(|e| Error::yaml(path.display(), e))
+}
+
+/// Write the user configuration file
+pub fn write_file(values: &BTreeMap<String, serde_yaml::Value>) -> Result<()> {
+    let path = settings_file()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_yaml::to_string(values)
+        .map_err(|e| Error::Other(format!("Failed to serialize settings: {}", e)))?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+impl Settings {
+    /// Read the settings from the environment, then the user configuration file
+    pub fn load() -> Result<Self> {
+        let path = settings_file()?;
+        let file = read_file()?;
+        let mut values = BTreeMap::new();
+
+        for key in KEYS {
+            // A bad variable shouldn't stop every command, fall back to the file instead
+            if let Ok(value) = env::var(key.env)
+                && !value.is_empty()
+            {
+                match key.parse(&value) {
+                    Ok(_) => {
+                        values.insert(key.name, (value, Origin::Env(key.env.to_string())));
+                        continue;
+                    }
+                    Err(e) => eprintln!(
+                        "{}",
+                        format!("Warning: {}, ignoring {}", e, key.env).yellow()
+                    ),
+                }
+            }
+            if let Some(value) = file.get(key.name) {
+                let value = match value {
+                    serde_yaml::Value::String(s) => s.clone(),
+                    serde_yaml::Value::Number(n) => n.to_string(),
+                    serde_yaml::Value::Bool(b) => b.to_string(),
+                    _ => {
+                        return Err(Error::Other(format!(
+                            "Invalid value for {} in {}",
+                            key.name,
+                            path.display()
+                        )));
+                    }
+                };
+                key.parse(&value)
+                    .map_err(|e| Error::Other(format!("{} (in {})", e, path.display())))?;
+                values.insert(key.name, (value, Origin::File(path.clone())));
+            }
+        }
+
+        Ok(Settings {
+            values,
+            rewrites: read_rules(&file, "rewrites", &path)?,
+            mirrors: read_rules(&file, "mirrors", &path)?,
+            auth: read_rules::<BTreeMap<String, HostAuth>>(&file, "auth", &path)?
+                .into_iter()
+                .map(|(host, auth)| (host.to_lowercase(), auth))
+                .collect(),
+        })
+    }
+
+    /// The value of a setting, None if it is not set
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|(value, _)| value.as_str())
+    }
+
+    /// The effective value of a setting, including defaults, and where it comes from
+    pub fn effective(&self, name: &str) -> Option<(String, Origin)> {
+        if let Some(value) = self.values.get(name) {
+            return Some(value.clone());
+        }
+        let default = match name {
+            "cache_dir" => default_cache_dir().ok().map(|p| p.display().to_string()),
+            "jobs" => Some(DEFAULT_JOBS.to_string()),
+            "color" => Some("auto".to_string()),
+            "progress" => Some("false".to_string()),
+            "git" => Some("git".to_string()),
+            _ => None,
+        };
+        default.map(|value| (value, Origin::Default))
+    }
+
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        self.get("cache_dir").map(PathBuf::from)
+    }
+
+    pub fn jobs(&self) -> usize {
+        self.get("jobs")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_JOBS)
+    }
+
+    pub fn registry(&self) -> Option<&str> {
+        self.get("registry")
+    }
+
+    pub fn progress(&self) -> bool {
+        self.get("progress") == Some("true")
+    }
+
+    pub fn git(&self) -> &str {
+        self.get("git").unwrap_or("git")
+    }
+
+    pub fn godot(&self) -> Option<&str> {
+        self.get("godot")
+    }
+
+    pub fn rewrites(&self) -> &BTreeMap<String, String> {
+        &self.rewrites
+    }
+
+    pub fn mirrors(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.mirrors
+    }
+
+    /// Hosts with an `auth` entry
+    pub fn auth_hosts(&self) -> impl Iterator<Item = &str> {
+        self.auth.keys().map(String::as_str)
+    }
+
+    /// The token of a host: from the variable named by `token_env`, then from
+    /// `GDAM_TOKEN_<HOST>`, then from the file
+    pub fn token(&self, host: &str) -> Option<Token> {
+        let auth = self.auth.get(host).cloned().unwrap_or_default();
+        let from_env = |var: &str| {
+            env::var(var)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .map(|secret| (secret, Origin::Env(var.to_string())))
+        };
+        let (secret, origin) = auth
+            .token_env
+            .as_deref()
+            .and_then(from_env)
+            .or_else(|| from_env(&token_env_var(host)))
+            .or_else(|| {
+                let path = settings_file().ok()?;
+                auth.token.clone().map(|t| (t, Origin::File(path)))
+            })?;
+        Some(Token {
+            username: auth
+                .username
+                .unwrap_or_else(|| DEFAULT_TOKEN_USERNAME.to_string()),
+            secret,
+            origin,
+        })
+    }
+
+    /// The SSH private key of a host, with `~` expanded
+    pub fn ssh_key(&self, host: &str) -> Option<PathBuf> {
+        let key = self.auth.get(host)?.ssh_key.as_deref()?;
+        match key.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+            None => Some(PathBuf::from(key)),
+        }
+    }
+}
+
+/// Environment variable holding the token of a host, e.g. `GDAM_TOKEN_GITHUB_COM`
+pub fn token_env_var(host: &str) -> String {
+    let host: String = host
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", TOKEN_ENV_PREFIX, host)
+}
+
+/// Read the rules under an entry of the user configuration file
+fn read_rules<T: serde::de::DeserializeOwned + Default>(
+    file: &BTreeMap<String, serde_yaml::Value>,
+    name: &str,
+    path: &Path,
+) -> Result<T> {
+    match file.get(name) {
+        Some(value) => serde_yaml::from_value(value.clone())
+            .map_err(|e| Error::Other(format!("Invalid {} in {}: {}", name, path.display(), e))),
+        None => Ok(T::default()),
+    }
+}
+
+/// The system cache directory for gdam
+pub fn default_cache_dir() -> Result<PathBuf> {
+    let cache_dir = dirs::cache_dir()
+        .ok_or_else(|| Error::Other("Cannot find system cache directory".to_string()))?;
+    Ok(cache_dir.join("gdam"))
+}
@@ -1,119 +1,366 @@
-use std::fs::{self, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::error::{Error, Result};
+use crate::project::{GODOT_PROJECT_FILE, Project};
+use crate::settings;
+use crate::version::Version;
 
-const GODOT_PROJECT_FILE: &str = "project.godot";
-const GITIGNORE_FILE: &str = ".gitignore";
+/// Check if the given path is a Godot project directory
+pub fn is_godot_project<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref().join(GODOT_PROJECT_FILE).exists()
+}
+
+/// Detect the Godot engine version of a project. When project.godot only gives
+/// the major version, the configured Godot executable is asked for the rest.
+pub fn get_godot_version(project: &Project) -> Option<Version> {
+    let content = fs::read_to_string(project.godot_file()).ok()?;
+    let version = parse_godot_version(&content)?;
 
-/// Check if current directory is a Godot project
-pub fn ensure_in_godot_project() -> Result<()> {
-    if !is_godot_project(".") {
-        return Err(Error::NotInProject);
+    let has_features = content
+        .lines()
+        .any(|l| l.trim_start().starts_with("config/features="));
+    if !has_features
+        && let Some(binary) = godot_binary_version()
+        && binary.major == version.major
+    {
+        return Some(binary);
     }
-    Ok(())
+    Some(version)
 }
 
-/// Check if the given path is a Godot project directory
-pub fn is_godot_project<P: AsRef<Path>>(path: P) -> bool {
-    path.as_ref().join(GODOT_PROJECT_FILE).exists()
+/// Version of the Godot executable set in the user settings, from `godot --version`
+/// output like `4.2.1.stable.official.b09f793f5`
+fn godot_binary_version() -> Option<Version> {
+    let godot = settings::current().godot()?;
+    let output = Command::new(godot).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Version::parse(&String::from_utf8_lossy(&output.stdout))
 }
 
-/// Get the gdam cache directory
-pub fn get_cache_dir() -> Result<PathBuf> {
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| Error::Other("Cannot find system cache directory".to_string()))?;
-    let gdam_cache = cache_dir.join("gdam");
-    fs::create_dir_all(&gdam_cache)?;
-    Ok(gdam_cache)
+/// Detect the Godot engine version from project.godot contents.
+///
+/// Prefers the engine version listed in `config/features` and falls back to
+/// `config_version` (5 is Godot 4, 4 is Godot 3, 3 is Godot 2), which only
+/// gives the major version.
+pub fn parse_godot_version(content: &str) -> Option<Version> {
+    let mut config_version = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if let Some(features) = line.strip_prefix("config/features=") {
+            let engine = features.split('"').skip(1).step_by(2).find_map(|feature| {
+                let (major, minor) = feature.split_once('.')?;
+                if major.chars().all(|c| c.is_ascii_digit())
+                    && minor.chars().all(|c| c.is_ascii_digit())
+                {
+                    Version::parse(feature)
+                } else {
+                    None
+                }
+            });
+            if engine.is_some() {
+                return engine;
+            }
+        } else if let Some(value) = line.strip_prefix("config_version=") {
+            config_version = value.trim().parse::<u64>().ok();
+        }
+    }
+
+    config_version.filter(|v| *v >= 3).map(|v| Version {
+        major: v - 1,
+        minor: 0,
+        patch: 0,
+    })
 }
 
-/// Copy a directory recursively
-pub fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
-    if !src.is_dir() {
+/// Check an autoload can be written to project.godot as is: the name must be an
+/// identifier, and the path must not end the quoted value or the line
+pub fn check_autoload(name: &str, path: &str) -> Result<()> {
+    let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier {
         return Err(Error::Other(format!(
-            "Source is not a directory: {:?}",
-            src
+            "Invalid autoload name {:?}: use letters, digits and underscores",
+            name
         )));
     }
+    if path.contains(['"', '\\', '\n', '\r']) {
+        return Err(Error::Other(format!(
+            "Invalid path of autoload '{}': {:?}",
+            name, path
+        )));
+    }
+    Ok(())
+}
 
-    fs::create_dir_all(dst)?;
+/// Register autoload singletons in project.godot, replacing entries with the same name
+pub fn register_autoloads(project: &Project, autoloads: &[(String, String)]) -> Result<()> {
+    if autoloads.is_empty() {
+        return Ok(());
+    }
+    for (name, path) in autoloads {
+        check_autoload(name, path)?;
+    }
 
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let file_type = entry.file_type()?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
+    let content = fs::read_to_string(project.godot_file())?;
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
 
-        if file_type.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)?;
+    let section = lines.iter().position(|l| l.trim() == "[autoload]");
+    let section = match section {
+        Some(index) => index,
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[autoload]".to_string());
+            lines.push(String::new());
+            lines.len() - 2
+        }
+    };
+
+    for (name, path) in autoloads {
+        let entry = format!("{}=\"*{}\"", name, path);
+        let section_end = lines[section + 1..]
+            .iter()
+            .position(|l| l.trim_start().starts_with('['))
+            .map(|i| section + 1 + i)
+            .unwrap_or(lines.len());
+
+        let existing = lines[section + 1..section_end]
+            .iter()
+            .position(|l| l.split_once('=').is_some_and(|(k, _)| k.trim() == name));
+        match existing {
+            Some(i) => lines[section + 1 + i] = entry,
+            None => {
+                // Insert after the last non-blank line of the section
+                let mut insert_at = section_end;
+                while insert_at > section + 1 && lines[insert_at - 1].trim().is_empty() {
+                    insert_at -= 1;
+                }
+                // Keep the blank line Godot writes after the section header
+                if insert_at == section + 1 && lines.get(insert_at).is_some_and(|l| l.is_empty()) {
+                    insert_at += 1;
+                }
+                lines.insert(insert_at, entry);
+            }
         }
     }
 
+    fs::write(project.godot_file(), lines.join("\n") + "\n")?;
     Ok(())
 }
 
-/// Check if .gitignore has global addons ignore pattern
-pub fn has_global_addons_ignore() -> bool {
-    let gitignore_path = Path::new(GITIGNORE_FILE);
-    if !gitignore_path.exists() {
-        return false;
-    }
-
-    let file = match fs::File::open(gitignore_path) {
-        Ok(f) => f,
-        Err(_) => return false,
+/// Remove autoload singletons pointing into an addon's directory.
+/// Returns the names of the removed autoloads.
+pub fn remove_addon_autoloads(project: &Project, addon_name: &str) -> Result<Vec<String>> {
+    let content = match fs::read_to_string(project.godot_file()) {
+        Ok(c) => c,
+        Err(_) => return Ok(Vec::new()),
     };
 
-    for line in BufReader::new(file).lines().flatten() {
+    let prefix = format!("res://addons/{}/", addon_name);
+    let mut in_autoload_section = false;
+    let mut removed = Vec::new();
+    let mut kept = Vec::new();
+
+    for line in content.lines() {
         let trimmed = line.trim();
-        if trimmed == "addons/" || trimmed == "addons/*" || trimmed == "addons" {
-            return true;
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            in_autoload_section = trimmed == "[autoload]";
+        } else if in_autoload_section
+            && let Some((name, value)) = trimmed.split_once('=')
+            && value
+                .trim_matches('"')
+                .trim_start_matches('*')
+                .starts_with(&prefix)
+        {
+            removed.push(name.trim().to_string());
+            continue;
         }
+        kept.push(line);
     }
 
-    false
+    if removed.is_empty() {
+        return Ok(removed);
+    }
+
+    // Drop the section if no autoloads are left
+    if let Some(section) = kept.iter().position(|l| l.trim() == "[autoload]") {
+        let section_end = kept[section + 1..]
+            .iter()
+            .position(|l| l.trim_start().starts_with('['))
+            .map(|i| section + 1 + i)
+            .unwrap_or(kept.len());
+        if kept[section + 1..section_end]
+            .iter()
+            .all(|l| l.trim().is_empty())
+        {
+            let start = if section > 0 && kept[section - 1].trim().is_empty() {
+                section - 1
+            } else {
+                section
+            };
+            kept.drain(start..section_end);
+        }
+    }
+
+    fs::write(project.godot_file(), kept.join("\n") + "\n")?;
+    Ok(removed)
 }
 
-/// Update .gitignore to add entries if they don't exist
-pub fn update_gitignore(entries: &[String]) -> Result<()> {
-    let gitignore_path = Path::new(GITIGNORE_FILE);
-
-    // Read existing entries
-    let existing: Vec<String> = if gitignore_path.exists() {
-        let file = fs::File::open(gitignore_path)?;
-        BufReader::new(file)
-            .lines()
-            .filter_map(|l| l.ok())
-            .collect()
-    } else {
-        Vec::new()
+/// Match a path against a glob pattern.
+///
+/// `*` and `?` match within a path segment, `**` matches across segments.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(p: &[u8], s: &[u8]) -> bool {
+        match p.first() {
+            None => s.is_empty(),
+            Some(b'*') if p.get(1) == Some(&b'*') => {
+                // `**/` also matches zero directories
+                let rest = &p[2..];
+                let rest_no_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+                (0..=s.len()).any(|i| matches(rest, &s[i..])) || matches(rest_no_slash, s)
+            }
+            Some(b'*') => (0..=s.len())
+                .take_while(|&i| i == 0 || s[i - 1] != b'/')
+                .any(|i| matches(&p[1..], &s[i..])),
+            Some(b'?') => s.first().is_some_and(|c| *c != b'/') && matches(&p[1..], &s[1..]),
+            Some(c) => s.first() == Some(c) && matches(&p[1..], &s[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+/// Check if a path relative to a directory is excluded by gitignore-style patterns.
+///
+/// Patterns without a slash match a file or directory name at any depth,
+/// patterns with a slash match the whole relative path.
+pub fn is_excluded(patterns: &[String], rel_path: &str, is_dir: bool) -> bool {
+    patterns.iter().any(|pattern| {
+        let (pattern, dir_only) = match pattern.strip_suffix('/') {
+            Some(p) => (p, true),
+            None => (pattern.as_str(), false),
+        };
+        if dir_only && !is_dir {
+            return false;
+        }
+        if pattern.contains('/') {
+            glob_match(pattern.trim_start_matches('/'), rel_path)
+        } else {
+            let name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+            glob_match(pattern, name)
+        }
+    })
+}
+
+/// Get the gdam cache directory
+pub fn get_cache_dir() -> Result<PathBuf> {
+    let gdam_cache = match settings::current().cache_dir() {
+        Some(dir) => dir,
+        None => settings::default_cache_dir()?,
     };
+    fs::create_dir_all(&gdam_cache)?;
+    Ok(gdam_cache)
+}
 
-    // Find new entries to add
-    let new_entries: Vec<&String> = entries.iter().filter(|e| !existing.contains(e)).collect();
+/// Check if the user can answer questions, i.e. stdin is a terminal
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal()
+}
 
-    if new_entries.is_empty() {
-        return Ok(());
+/// Ask the user a yes/no question on the terminal, defaulting to no.
+/// Questions go to stderr to keep stdout for results.
+pub fn confirm(prompt: &str) -> Result<bool> {
+    eprint!("{} [y/N] ", prompt);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim().to_ascii_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// Ask the user for a line of text on the terminal, empty if nothing was entered
+pub fn prompt(prompt: &str) -> Result<String> {
+    eprint!("{} ", prompt);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Format the time since a unix timestamp, e.g. `3 days ago`
+pub fn format_age(timestamp: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(timestamp);
+    let secs = (now - timestamp).max(0);
+
+    let (value, unit) = match secs {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s if s < 86400 * 30 => (s / 86400, "day"),
+        s if s < 86400 * 365 => (s / (86400 * 30), "month"),
+        s => (s / (86400 * 365), "year"),
+    };
+
+    if value == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", value, unit)
     }
+}
 
-    // Append new entries
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(gitignore_path)?;
+/// Copy a directory recursively, skipping `.git` and entries matching the exclude patterns
+pub fn copy_dir_excluding(src: &Path, dst: &Path, exclude: &[String]) -> Result<()> {
+    fn copy(src: &Path, dst: &Path, rel: &str, exclude: &[String]) -> Result<()> {
+        fs::create_dir_all(dst)?;
 
-    // Add newline if file doesn't end with one
-    if !existing.is_empty() {
-        writeln!(file)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Addons at a repository root sit next to its `.git` directory
+            if name == ".git" {
+                continue;
+            }
+            let rel_path = if rel.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", rel, name)
+            };
+
+            if is_excluded(exclude, &rel_path, file_type.is_dir()) {
+                continue;
+            }
+
+            let dst_path = dst.join(&name);
+            if file_type.is_dir() {
+                copy(&entry.path(), &dst_path, &rel_path, exclude)?;
+            } else {
+                fs::copy(entry.path(), &dst_path)?;
+            }
+        }
+
+        Ok(())
     }
 
-    for entry in new_entries {
-        writeln!(file, "{}", entry)?;
+    if !src.is_dir() {
+        return Err(Error::Other(format!(
+            "Source is not a directory: {:?}",
+            src
+        )));
     }
 
-    Ok(())
+    copy(src, dst, "", exclude)
 }
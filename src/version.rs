@@ -0,0 +1,234 @@
+use std::fmt;
+
+/// A loosely parsed semantic version, as found in git tags and plugin.cfg files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    /// Parse versions like `1.2.3`, `v1.2` or `2.0.1-beta`.
+    /// Missing components default to zero and pre-release suffixes are ignored.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix('v')
+            .or_else(|| s.strip_prefix('V'))
+            .unwrap_or(s);
+
+        let mut numbers = Vec::new();
+        for part in s.split('.').take(3) {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if digits.is_empty() {
+                break;
+            }
+            numbers.push(digits.parse().ok()?);
+            if digits.len() != part.len() {
+                // Suffix like `3-beta`, stop here
+                break;
+            }
+        }
+
+        if numbers.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            major: numbers[0],
+            minor: numbers.get(1).copied().unwrap_or(0),
+            patch: numbers.get(2).copied().unwrap_or(0),
+        })
+    }
+}
+
+/// Check if a version has a pre-release suffix, like `2.0.1-beta` or `v3.0rc1`.
+/// Build metadata after `+` and Godot's `-stable` suffix don't count.
+pub fn is_prerelease(s: &str) -> bool {
+    let s = s.trim();
+    let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+    let suffix = s.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let suffix = suffix
+        .split('+')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let label = suffix.trim_start_matches(['-', '_', '.']);
+    match label {
+        "" | "stable" => false,
+        _ => {
+            suffix.starts_with('-')
+                || ["alpha", "beta", "rc", "pre", "dev"]
+                    .iter()
+                    .any(|p| label.starts_with(p))
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Matches all versions starting with the given components, e.g. `4.2` or `4.x`
+    Prefix,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+/// A version requirement like `>=4.2, <5`, `4.x` or `4.3`
+#[derive(Debug, Clone)]
+pub struct VersionReq {
+    comparators: Vec<(Op, Version, usize)>, // (op, version, number of components given)
+}
+
+impl VersionReq {
+    /// Parse a comma separated list of comparators
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut comparators = Vec::new();
+
+        for part in s.split(',') {
+            let part = part.trim();
+            let (op, rest) = if let Some(rest) = part.strip_prefix(">=") {
+                (Op::GreaterEq, rest)
+            } else if let Some(rest) = part.strip_prefix("<=") {
+                (Op::LessEq, rest)
+            } else if let Some(rest) = part.strip_prefix('>') {
+                (Op::Greater, rest)
+            } else if let Some(rest) = part.strip_prefix('<') {
+                (Op::Less, rest)
+            } else {
+                (Op::Prefix, part.strip_prefix('=').unwrap_or(part))
+            };
+
+            let rest = rest.trim();
+            let components = rest
+                .split('.')
+                .take(3)
+                .take_while(|c| !c.is_empty() && c.chars().all(|ch| ch.is_ascii_digit()))
+                .count();
+            if components == 0 {
+                return None;
+            }
+            comparators.push((op, Version::parse(rest)?, components));
+        }
+
+        Some(Self { comparators })
+    }
+
+    /// Check if a version satisfies every comparator
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators.iter().all(|(op, req, components)| {
+            let truncated = truncate(version, *components);
+            match op {
+                Op::Prefix => truncated == *req,
+                Op::Greater => truncated > *req,
+                Op::GreaterEq => truncated >= *req,
+                Op::Less => truncated < *req,
+                Op::LessEq => truncated <= *req,
+            }
+        })
+    }
+}
+
+/// Keep only the first `components` components of a version
+fn truncate(version: &Version, components: usize) -> Version {
+    Version {
+        major: version.major,
+        minor: if components > 1 { version.minor } else { 0 },
+        patch: if components > 2 { version.patch } else { 0 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    fn matches(req: &str, version: &str) -> bool {
+        VersionReq::parse(req)
+            .unwrap()
+            .matches(&Version::parse(version).unwrap())
+    }
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(Version::parse("1.2.3"), Some(v(1, 2, 3)));
+        assert_eq!(Version::parse("v1.2"), Some(v(1, 2, 0)));
+        assert_eq!(Version::parse("V4"), Some(v(4, 0, 0)));
+        assert_eq!(Version::parse(" 2.0.1-beta "), Some(v(2, 0, 1)));
+        assert_eq!(Version::parse("1.2-rc1.5"), Some(v(1, 2, 0)));
+        assert_eq!(Version::parse("1.2.3.4"), Some(v(1, 2, 3)));
+        assert_eq!(Version::parse("3.x"), Some(v(3, 0, 0)));
+    }
+
+    #[test]
+    fn parse_invalid_versions() {
+        assert_eq!(Version::parse(""), None);
+        assert_eq!(Version::parse("main"), None);
+        assert_eq!(Version::parse("v"), None);
+        assert_eq!(Version::parse("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn prereleases() {
+        assert!(is_prerelease("2.0.1-beta"));
+        assert!(is_prerelease("v1.2-rc1.5"));
+        assert!(is_prerelease("v3.0rc1"));
+        assert!(is_prerelease("1.0.0-alpha+build.5"));
+        assert!(!is_prerelease("1.2.3"));
+        assert!(!is_prerelease("v4.2-stable"));
+        assert!(!is_prerelease("1.0.0+build.5"));
+        assert!(!is_prerelease("3.x"));
+    }
+
+    #[test]
+    fn versions_order() {
+        assert!(v(1, 10, 0) > v(1, 9, 9));
+        assert!(v(2, 0, 0) > v(1, 99, 99));
+        assert_eq!(v(1, 2, 0).to_string(), "1.2.0");
+    }
+
+    #[test]
+    fn prefix_requirements() {
+        assert!(matches("4", "4.3.1"));
+        assert!(matches("4.x", "4.0"));
+        assert!(matches("4.2", "4.2.9"));
+        assert!(!matches("4.2", "4.3"));
+        assert!(matches("=4.2.1", "4.2.1"));
+        assert!(!matches("=4.2.1", "4.2.2"));
+    }
+
+    #[test]
+    fn range_requirements() {
+        assert!(matches(">=4.2, <5", "4.2"));
+        assert!(matches(">=4.2, <5", "4.9.9"));
+        assert!(!matches(">=4.2, <5", "4.1.9"));
+        assert!(!matches(">=4.2, <5", "5.0"));
+        assert!(matches(">3", "4.0"));
+        assert!(!matches(">4", "4.5"));
+        assert!(matches("<=4.2", "4.2.7"));
+        assert!(!matches("<4.2", "4.2.7"));
+    }
+
+    #[test]
+    fn parse_invalid_requirements() {
+        assert!(VersionReq::parse("").is_none());
+        assert!(VersionReq::parse(">=").is_none());
+        assert!(VersionReq::parse("4.2,").is_none());
+        assert!(VersionReq::parse("latest").is_none());
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Addon, Config};
+use crate::error::{Error, Result};
+use crate::git::same_source;
+use crate::utils::{glob_match, is_godot_project};
+
+pub const WORKSPACE_FILE: &str = "gdam-workspace.yaml";
+
+/// Workspace manifest at the root of a repository holding several Godot projects
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    /// Member project directories relative to the workspace root.
+    /// `*` and `?` match directory names, e.g. `tests/*`.
+    pub members: Vec<String>,
+    /// Addons installed in every member, locked to the same commit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addons: Vec<Addon>,
+}
+
+impl Workspace {
+    /// Load the workspace manifest of a workspace root
+    pub fn load(root: &Path) -> Result<Self> {
+        let content = fs::read_to_string(root.join(WORKSPACE_FILE))?;
+        serde_yaml::from_str(&content).map_err(|e| Error::yaml(WORKSPACE_FILE, e))
+    }
+
+    /// Save the workspace manifest to a workspace root
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self)
+            .map_err(|e| Error::Other(format!("Failed to serialize workspace: {}", e)))?;
+        fs::write(root.join(WORKSPACE_FILE), content)?;
+        Ok(())
+    }
+
+    /// List the member project directories relative to the root, in declaration order
+    pub fn member_dirs(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for member in &self.members {
+            let member = member.trim_end_matches('/');
+            let (parent, name) = match member.rsplit_once('/') {
+                Some((parent, name)) => (parent, name),
+                None => ("", member),
+            };
+
+            let found: Vec<PathBuf> = if name.contains(['*', '?']) {
+                let mut matches: Vec<PathBuf> = fs::read_dir(root.join(parent))?
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| {
+                        glob_match(name, &entry.file_name().to_string_lossy())
+                            && is_godot_project(entry.path())
+                    })
+                    .map(|entry| Path::new(parent).join(entry.file_name()))
+                    .collect();
+                matches.sort();
+                matches
+            } else if is_godot_project(root.join(member)) {
+                vec![PathBuf::from(member)]
+            } else {
+                return Err(Error::Other(format!(
+                    "Workspace member '{}' is not a Godot project",
+                    member
+                )));
+            };
+
+            for dir in found {
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Copy the shared addons into the config of a member, returns whether it changed
+    pub fn apply_shared(&self, config: &mut Config, member: &str) -> Result<bool> {
+        let mut changed = false;
+        for shared in &self.addons {
+            match config.addons.iter_mut().find(|a| a.name == shared.name) {
+                Some(existing) if !same_source(&existing.source, &shared.source) => {
+                    return Err(Error::Other(format!(
+                        "{} installs addons/{} from {}, but the workspace shares it from {}",
+                        member, shared.name, existing.source, shared.source
+                    )));
+                }
+                Some(existing) => {
+                    if existing.checksum != shared.checksum {
+                        existing.checksum = shared.checksum.clone();
+                        existing.version = shared.version.clone();
+                        changed = true;
+                    }
+                }
+                None => {
+                    config.addons.push(shared.clone());
+                    changed = true;
+                }
+            }
+        }
+        Ok(changed)
+    }
+}